@@ -8,5 +8,37 @@ fn parse(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, parse);
-criterion_main!(benches);
\ No newline at end of file
+fn large_jar() -> String {
+    (0..10000)
+        .map(|i| {
+            format!(
+                ".pixiv.net\tTRUE\t/\tTRUE\t1784339332\tname_{}\tvalue_{}\n",
+                i, i
+            )
+        })
+        .collect()
+}
+
+fn reload(c: &mut Criterion) {
+    let text = large_jar();
+    let bytes = nescookie::to_bytes(&nescookie::parse(&text).unwrap());
+    c.bench_function("reload text", |b| {
+        b.iter(|| black_box(nescookie::parse(&text).unwrap()))
+    });
+    c.bench_function("reload bytes", |b| {
+        b.iter(|| black_box(nescookie::from_bytes(&bytes).unwrap()))
+    });
+}
+
+fn count(c: &mut Criterion) {
+    let text = large_jar();
+    c.bench_function("count", |b| {
+        b.iter(|| black_box(nescookie::count(&text).unwrap()))
+    });
+    c.bench_function("count parsed", |b| {
+        b.iter(|| black_box(nescookie::parse(&text).unwrap().iter().count()))
+    });
+}
+
+criterion_group!(benches, parse, reload, count);
+criterion_main!(benches);
@@ -0,0 +1,74 @@
+use crate::{error::Error, CookieJarBuilder};
+#[cfg(any(feature = "zip", feature = "tar"))]
+use std::io::Read;
+use std::{fs::File, path::Path};
+
+impl CookieJarBuilder {
+    /// Opens a zip archive with `archive_path` and parses its entry `entry_name` as cookies
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let jar = CookieJarBuilder::new()
+    ///     .open_zip("tests/cookies.zip", "cookies.txt")
+    ///     .unwrap()
+    ///     .finish();
+    /// ```
+    #[cfg(feature = "zip")]
+    pub fn open_zip(self, archive_path: impl AsRef<Path>, entry_name: &str) -> Result<Self, Error> {
+        let archive_path = archive_path.as_ref();
+        let file = File::open(archive_path).map_err(Error::open(archive_path))?;
+        let mut archive = zip::ZipArchive::new(file)?;
+        let mut s = String::new();
+        archive.by_name(entry_name)?.read_to_string(&mut s)?;
+        self.parse_source(&s, Some(archive_path))
+    }
+    /// Opens a zstd compressed file with `path` and parses it as cookies
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let jar = CookieJarBuilder::new()
+    ///     .open_zst("tests/cookies.txt.zst")
+    ///     .unwrap()
+    ///     .finish();
+    /// ```
+    #[cfg(feature = "zstd")]
+    pub fn open_zst(self, path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(Error::open(path))?;
+        let bytes = zstd::stream::decode_all(file).map_err(|e| Error::Archive(Box::new(e)))?;
+        let s = String::from_utf8(bytes)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+        self.parse_source(&s, Some(path))
+    }
+    /// Opens a tar archive with `archive_path` and parses its entry `entry_name` as cookies
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let jar = CookieJarBuilder::new()
+    ///     .open_tar("tests/cookies.tar", "cookies.txt")
+    ///     .unwrap()
+    ///     .finish();
+    /// ```
+    #[cfg(feature = "tar")]
+    pub fn open_tar(self, archive_path: impl AsRef<Path>, entry_name: &str) -> Result<Self, Error> {
+        let archive_path = archive_path.as_ref();
+        let tar_error = |e: std::io::Error| Error::Archive(Box::new(e));
+        let file = File::open(archive_path).map_err(Error::open(archive_path))?;
+        let mut archive = tar::Archive::new(file);
+        for entry in archive.entries().map_err(tar_error)? {
+            let mut entry = entry.map_err(tar_error)?;
+            if entry.path().map_err(tar_error)? == Path::new(entry_name) {
+                let mut s = String::new();
+                entry.read_to_string(&mut s)?;
+                return self.parse_source(&s, Some(archive_path));
+            }
+        }
+        Err(tar_error(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("no entry named {} in the archive", entry_name),
+        )))
+    }
+}
@@ -0,0 +1,108 @@
+use crate::{
+    error::{BinaryError, Error},
+    timestamp,
+};
+use cookie::{Cookie, CookieJar};
+
+const MAGIC: &[u8; 4] = b"NES\x01";
+
+const SECURE: u8 = 1;
+const HTTP_ONLY: u8 = 1 << 1;
+
+/// Encodes a [`CookieJar`](cookie::CookieJar) into a compact binary form
+///
+/// Every cookie is stored as its name, value, domain and path, each prefixed with
+/// its length as a little-endian `u32`, followed by a flag byte and the expiration
+/// as a little-endian `i64` (`0` for session cookies).
+/// An empty domain or path is read back as unset.
+///
+/// ```
+/// let jar = nescookie::open("tests/cookies.txt").unwrap();
+/// let bytes = nescookie::to_bytes(&jar);
+/// let jar = nescookie::from_bytes(&bytes).unwrap();
+/// ```
+pub fn to_bytes(jar: &CookieJar) -> Vec<u8> {
+    let mut buf = MAGIC.to_vec();
+    for c in jar.iter() {
+        write_str(&mut buf, c.name());
+        write_str(&mut buf, c.value());
+        write_str(&mut buf, c.domain().unwrap_or_default());
+        write_str(&mut buf, c.path().unwrap_or_default());
+        let mut flags = 0;
+        if c.secure() == Some(true) {
+            flags |= SECURE;
+        }
+        if c.http_only() == Some(true) {
+            flags |= HTTP_ONLY;
+        }
+        buf.push(flags);
+        let expiration = c.expires_datetime().map_or(0, |t| t.unix_timestamp());
+        buf.extend_from_slice(&expiration.to_le_bytes());
+    }
+    buf
+}
+
+/// Decodes a [`CookieJar`](cookie::CookieJar) produced by [`to_bytes`]
+///
+/// ```
+/// use nescookie::error::{BinaryError, Error};
+///
+/// assert!(matches!(
+///     nescookie::from_bytes(b"NES"),
+///     Err(Error::Binary(BinaryError::InvalidHeader))
+/// ));
+/// ```
+pub fn from_bytes(mut bytes: &[u8]) -> Result<CookieJar, Error> {
+    if !bytes.starts_with(MAGIC) {
+        return Err(BinaryError::InvalidHeader.into());
+    }
+    bytes = &bytes[MAGIC.len()..];
+    let mut jar = CookieJar::new();
+    while !bytes.is_empty() {
+        let name = read_str(&mut bytes)?;
+        let value = read_str(&mut bytes)?;
+        let domain = read_str(&mut bytes)?;
+        let path = read_str(&mut bytes)?;
+        let flags = take(&mut bytes, 1)?[0];
+        let mut expiration = [0; 8];
+        expiration.copy_from_slice(take(&mut bytes, 8)?);
+        let mut cookie = Cookie::build(name, value)
+            .secure(flags & SECURE != 0)
+            .expires(match i64::from_le_bytes(expiration) {
+                0 => None,
+                exp => Some(timestamp(exp).ok_or(BinaryError::InvalidExpiration(exp))?),
+            });
+        if !domain.is_empty() {
+            cookie = cookie.domain(domain);
+        }
+        if !path.is_empty() {
+            cookie = cookie.path(path);
+        }
+        if flags & HTTP_ONLY != 0 {
+            cookie = cookie.http_only(true);
+        }
+        jar.add(cookie.finish().into_owned());
+    }
+    Ok(jar)
+}
+
+fn write_str(buf: &mut Vec<u8>, s: &str) {
+    buf.extend_from_slice(&(s.len() as u32).to_le_bytes());
+    buf.extend_from_slice(s.as_bytes());
+}
+
+fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Result<&'a [u8], BinaryError> {
+    if bytes.len() < len {
+        return Err(BinaryError::UnexpectedEnd);
+    }
+    let (head, tail) = bytes.split_at(len);
+    *bytes = tail;
+    Ok(head)
+}
+
+fn read_str<'a>(bytes: &mut &'a [u8]) -> Result<&'a str, BinaryError> {
+    let mut len = [0; 4];
+    len.copy_from_slice(take(bytes, 4)?);
+    let s = take(bytes, u32::from_le_bytes(len) as usize)?;
+    std::str::from_utf8(s).map_err(|_| BinaryError::InvalidUtf8)
+}
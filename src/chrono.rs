@@ -0,0 +1,56 @@
+use crate::{timestamp_nanos, CookieJarBuilder, MAX_TIMESTAMP, MIN_TIMESTAMP};
+use ::chrono::{DateTime, TimeZone, Utc};
+use cookie::Cookie;
+use time::OffsetDateTime;
+
+/// Converts an [`OffsetDateTime`](time::OffsetDateTime) into a `chrono` datetime in UTC,
+/// returning `None` if it is out of the range of `chrono`
+///
+/// ```
+/// use nescookie::OffsetDateTime;
+///
+/// let t = nescookie::to_chrono(OffsetDateTime::from_unix_timestamp(1626662932)).unwrap();
+/// assert_eq!(t.timestamp(), 1626662932);
+/// ```
+pub fn to_chrono(t: OffsetDateTime) -> Option<DateTime<Utc>> {
+    Utc.timestamp_opt(t.unix_timestamp(), t.nanosecond())
+        .single()
+}
+
+/// Converts a `chrono` datetime into an [`OffsetDateTime`](time::OffsetDateTime),
+/// returning `None` if it is out of the years 1 to 9999
+///
+/// ```
+/// use chrono::{TimeZone, Utc};
+///
+/// let t = nescookie::from_chrono(Utc.timestamp_opt(1626662932, 0).unwrap()).unwrap();
+/// assert_eq!(t.unix_timestamp(), 1626662932);
+/// assert!(nescookie::from_chrono(Utc.with_ymd_and_hms(10000, 1, 1, 0, 0, 0).unwrap()).is_none());
+/// ```
+pub fn from_chrono<Tz: TimeZone>(t: DateTime<Tz>) -> Option<OffsetDateTime> {
+    timestamp_nanos(t.timestamp() as i128 * 1_000_000_000 + t.timestamp_subsec_nanos() as i128)
+}
+
+/// Returns the expiration of `cookie` as a `chrono` datetime, or `None` for session cookies
+///
+/// ```
+/// let jar = nescookie::open("tests/cookies.txt").unwrap();
+/// let exp = nescookie::expires_chrono(jar.get("PHPSESSID").unwrap()).unwrap();
+/// assert_eq!(exp.timestamp(), 1626662932);
+/// ```
+pub fn expires_chrono(cookie: &Cookie) -> Option<DateTime<Utc>> {
+    cookie.expires_datetime().and_then(to_chrono)
+}
+
+impl CookieJarBuilder {
+    /// Skips cookies that have expired at `now`, see [`skip_expired`](Self::skip_expired)
+    ///
+    /// A `now` out of the years 1 to 9999 is clamped to that range.
+    pub fn skip_expired_chrono<Tz: TimeZone>(self, now: DateTime<Tz>) -> Self {
+        let secs = now.timestamp();
+        let now = from_chrono(now).unwrap_or_else(|| {
+            OffsetDateTime::from_unix_timestamp(secs.clamp(MIN_TIMESTAMP, MAX_TIMESTAMP))
+        });
+        self.skip_expired(now)
+    }
+}
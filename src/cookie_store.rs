@@ -0,0 +1,41 @@
+use crate::timestamp;
+use ::cookie_store::{CookieDomain, CookieExpiration, CookieStore};
+use cookie::{Cookie, CookieJar};
+
+/// Collects the unexpired cookies of a `cookie_store::CookieStore` into a [`CookieJar`](cookie::CookieJar)
+///
+/// Domain cookies get a leading dot so they are written with the subdomain flag set,
+/// while host-only cookies keep the bare host.
+/// Cookies without a domain are skipped.
+///
+/// ```
+/// use cookie_store::CookieStore;
+///
+/// let store = CookieStore::default();
+/// let jar = nescookie::from_cookie_store(&store);
+/// assert_eq!(jar.iter().count(), 0);
+/// ```
+pub fn from_cookie_store(store: &CookieStore) -> CookieJar {
+    let mut jar = CookieJar::new();
+    for c in store.iter_unexpired() {
+        let domain = match &c.domain {
+            CookieDomain::HostOnly(host) => host.clone(),
+            CookieDomain::Suffix(suffix) => format!(".{}", suffix),
+            CookieDomain::NotPresent | CookieDomain::Empty => continue,
+        };
+        let expires = match c.expires {
+            CookieExpiration::AtUtc(t) => timestamp(t.unix_timestamp()),
+            CookieExpiration::SessionEnd => None,
+        };
+        let mut cookie = Cookie::build(c.name().to_owned(), c.value().to_owned())
+            .domain(domain)
+            .path(String::from(&c.path))
+            .secure(c.secure().unwrap_or_default())
+            .expires(expires);
+        if c.http_only() == Some(true) {
+            cookie = cookie.http_only(true);
+        }
+        jar.add(cookie.finish());
+    }
+    jar
+}
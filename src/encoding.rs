@@ -0,0 +1,29 @@
+use crate::{error::Error, CookieJarBuilder};
+use encoding_rs::Encoding;
+use std::{fs, path::Path};
+
+impl CookieJarBuilder {
+    /// Opens a file with `path`, decodes it with `encoding` and parses it as cookies
+    ///
+    /// A byte order mark, if present, takes precedence over `encoding`.
+    /// Malformed sequences are replaced with U+FFFD.
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let jar = CookieJarBuilder::new()
+    ///     .open_with_encoding("tests/cookies_sjis.txt", encoding_rs::SHIFT_JIS)
+    ///     .unwrap()
+    ///     .finish();
+    /// ```
+    pub fn open_with_encoding(
+        self,
+        path: impl AsRef<Path>,
+        encoding: &'static Encoding,
+    ) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let bytes = fs::read(path).map_err(Error::open(path))?;
+        let (s, _, _) = encoding.decode(&bytes);
+        self.parse_source(&s, Some(path))
+    }
+}
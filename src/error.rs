@@ -1,14 +1,58 @@
-use std::fmt::Display;
+use std::{fmt::Display, path::PathBuf};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ParseError {
     InvaildValue(String),
     TooFewFileds,
+    DuplicateName(String),
+    InvalidName { line: usize, name: String },
+    UnexpectedComment { line: usize },
+    LineTooLong { line: usize, len: usize },
+    WhitespaceLine,
+}
+/// Errors of decoding the binary form produced by [`to_bytes`](crate::to_bytes)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BinaryError {
+    InvalidHeader,
+    UnexpectedEnd,
+    InvalidUtf8,
+    InvalidExpiration(i64),
 }
 #[derive(Debug)]
 pub enum Error {
     ParseError(ParseError),
     IoError(std::io::Error),
+    Open {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    Archive(Box<dyn std::error::Error + Send + Sync>),
+    CountMismatch {
+        expected: usize,
+        found: usize,
+    },
+    NotNetscapeFormat,
+    InvalidUrl(Box<dyn std::error::Error + Send + Sync>),
+    InvalidDelimiter(char),
+    Database(Box<dyn std::error::Error + Send + Sync>),
+    Binary(BinaryError),
+}
+
+impl ParseError {
+    /// Returns a hint on how to fix the error
+    pub(crate) fn hint(&self) -> &'static str {
+        match self {
+            Self::InvaildValue(_) => {
+                "expected TRUE/FALSE, a unix timestamp or an RFC 3339 datetime"
+            }
+            Self::TooFewFileds => "expected 7 tab-separated fields",
+            Self::DuplicateName(_) => "use a different DuplicatePolicy to allow it",
+            Self::InvalidName { .. } => "expected a token without separators or control characters",
+            Self::UnexpectedComment { .. } => "only the header and #HttpOnly_ lines are allowed",
+            Self::LineTooLong { .. } => "raise the limit with max_line_len",
+            Self::WhitespaceLine => "remove the spaces or the line",
+        }
+    }
 }
 
 impl Display for ParseError {
@@ -16,18 +60,75 @@ impl Display for ParseError {
         match self {
             Self::InvaildValue(value) => write!(f, "InvalidValue: {}", value),
             Self::TooFewFileds => write!(f, "TooFewFields"),
-        }
+            Self::DuplicateName(name) => write!(f, "DuplicateName: {}", name),
+            Self::InvalidName { line, name } => {
+                write!(f, "InvalidName: {:?} at line {}", name, line)
+            }
+            Self::UnexpectedComment { line } => write!(f, "UnexpectedComment at line {}", line),
+            Self::LineTooLong { line, len } => {
+                write!(f, "LineTooLong: {} bytes at line {}", len, line)
+            }
+            Self::WhitespaceLine => write!(f, "WhitespaceLine: a line made only of whitespace"),
+        }?;
+        write!(f, " ({})", self.hint())
     }
 }
 
 impl std::error::Error for ParseError {}
 
+impl Display for BinaryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::InvalidHeader => write!(f, "InvalidHeader (expected data written by to_bytes)"),
+            Self::UnexpectedEnd => write!(f, "UnexpectedEnd (the data is truncated)"),
+            Self::InvalidUtf8 => write!(f, "InvalidUtf8 (a string is not UTF-8 encoded)"),
+            Self::InvalidExpiration(exp) => write!(
+                f,
+                "InvalidExpiration: {} (expected a unix timestamp of the years 1 to 9999)",
+                exp
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BinaryError {}
+
+impl Error {
+    /// Wraps an error of opening or reading the file or directory at `path`
+    pub(crate) fn open(path: &std::path::Path) -> impl FnOnce(std::io::Error) -> Self + '_ {
+        move |source| Self::Open {
+            path: path.to_owned(),
+            source,
+        }
+    }
+}
+
 impl From<std::io::Error> for Error {
     fn from(e: std::io::Error) -> Self {
         Self::IoError(e)
     }
 }
 
+#[cfg(feature = "zip")]
+impl From<zip::result::ZipError> for Error {
+    fn from(e: zip::result::ZipError) -> Self {
+        Self::Archive(Box::new(e))
+    }
+}
+
+#[cfg(feature = "rusqlite")]
+impl From<rusqlite::Error> for Error {
+    fn from(e: rusqlite::Error) -> Self {
+        Self::Database(Box::new(e))
+    }
+}
+
+impl From<BinaryError> for Error {
+    fn from(e: BinaryError) -> Self {
+        Self::Binary(e)
+    }
+}
+
 impl From<ParseError> for Error {
     fn from(e: ParseError) -> Self {
         Self::ParseError(e)
@@ -39,6 +140,34 @@ impl Display for Error {
         match self {
             Self::ParseError(e) => write!(f, "ParseError: {}", e),
             Self::IoError(e) => write!(f, "IoError: {}", e),
+            Self::Open { path, source } => write!(
+                f,
+                "OpenError: {}: {} (check that the file is readable)",
+                path.display(),
+                source
+            ),
+            Self::Archive(e) => write!(
+                f,
+                "ArchiveError: {} (check the archive and the entry name)",
+                e
+            ),
+            Self::CountMismatch { expected, found } => write!(
+                f,
+                "CountMismatch: expected {} cookies, found {}",
+                expected, found
+            ),
+            Self::NotNetscapeFormat => write!(
+                f,
+                "NotNetscapeFormat (expected a cookies.txt file with tab-separated fields)"
+            ),
+            Self::InvalidUrl(e) => write!(f, "InvalidUrl: {}", e),
+            Self::InvalidDelimiter(c) => write!(
+                f,
+                "InvalidDelimiter: {:?} (a line break cannot separate fields)",
+                c
+            ),
+            Self::Database(e) => write!(f, "DatabaseError: {}", e),
+            Self::Binary(e) => write!(f, "BinaryError: {}", e),
         }
     }
 }
@@ -47,7 +176,12 @@ impl std::error::Error for Error {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match self {
             Self::ParseError(e) => Some(e),
-            Self::IoError(e) => Some(e),
+            Self::IoError(e) | Self::Open { source: e, .. } => Some(e),
+            Self::Binary(e) => Some(e),
+            Self::Archive(e) | Self::InvalidUrl(e) | Self::Database(e) => Some(e.as_ref()),
+            Self::CountMismatch { .. } | Self::NotNetscapeFormat | Self::InvalidDelimiter(_) => {
+                None
+            }
         }
     }
 }
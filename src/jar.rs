@@ -0,0 +1,410 @@
+use crate::checked_add;
+use cookie::{Cookie, CookieJar};
+use std::{collections::BTreeMap, fmt::Write, time::Duration};
+use time::{Format, OffsetDateTime};
+
+/// Removes cookies that have expired at `now` from `jar`, returning how many were removed
+///
+/// Session cookies are always kept.
+///
+/// ```
+/// use nescookie::OffsetDateTime;
+///
+/// let mut jar = nescookie::open("tests/cookies.txt").unwrap();
+/// let removed = nescookie::prune_expired(&mut jar, OffsetDateTime::from_unix_timestamp(1700000000));
+/// assert_eq!(removed, 3);
+/// ```
+pub fn prune_expired(jar: &mut CookieJar, now: OffsetDateTime) -> usize {
+    let expired: Vec<_> = jar
+        .iter()
+        .filter(|c| matches!(c.expires_datetime(), Some(exp) if exp <= now))
+        .cloned()
+        .collect();
+    let count = expired.len();
+    for c in expired {
+        jar.remove(c);
+    }
+    count
+}
+
+/// Returns the earliest expiration among the cookies in `jar`, ignoring session cookies
+///
+/// ```
+/// let jar = nescookie::open("tests/cookies.txt").unwrap();
+/// let exp = nescookie::min_expiry(&jar).unwrap();
+/// assert_eq!(exp.unix_timestamp(), 1626662932);
+/// ```
+pub fn min_expiry(jar: &CookieJar) -> Option<OffsetDateTime> {
+    jar.iter().filter_map(|c| c.expires_datetime()).min()
+}
+
+/// Returns the seconds from `now` until `cookie` expires, or `None` for session cookies
+///
+/// The result is negative if the cookie has already expired.
+///
+/// ```
+/// use nescookie::{Cookie, OffsetDateTime};
+///
+/// let cookie = Cookie::build("a", "1")
+///     .expires(OffsetDateTime::from_unix_timestamp(1700000060))
+///     .finish();
+/// let now = OffsetDateTime::from_unix_timestamp(1700000000);
+/// assert_eq!(nescookie::seconds_until_expiry(&cookie, now), Some(60));
+/// ```
+pub fn seconds_until_expiry(cookie: &Cookie, now: OffsetDateTime) -> Option<i64> {
+    cookie
+        .expires_datetime()
+        .map(|exp| exp.unix_timestamp() - now.unix_timestamp())
+}
+
+/// Renders every cookie in `jar` on its own line for manual inspection
+///
+/// Cookies are sorted by name and expirations are shown as RFC 3339 datetimes,
+/// or `session` for session cookies.
+///
+/// ```
+/// let jar = nescookie::open("tests/cookies.txt").unwrap();
+/// println!("{}", nescookie::debug_dump(&jar));
+/// ```
+pub fn debug_dump(jar: &CookieJar) -> String {
+    let mut cookies: Vec<_> = jar.iter().collect();
+    cookies.sort_by(|a, b| a.name().cmp(b.name()));
+    let mut dump = String::new();
+    for c in cookies {
+        let expires = c
+            .expires_datetime()
+            .map_or_else(|| "session".to_owned(), |t| t.format(Format::Rfc3339));
+        let _ = writeln!(
+            dump,
+            "{}={}\tdomain={}\tpath={}\tsecure={}\thttp_only={}\texpires={}",
+            c.name(),
+            c.value(),
+            c.domain().unwrap_or_default(),
+            c.path().unwrap_or_default(),
+            c.secure().unwrap_or_default(),
+            c.http_only().unwrap_or_default(),
+            expires
+        );
+    }
+    dump
+}
+
+/// Estimates the storage used by `jar` in bytes
+///
+/// This sums the lengths of the names, values, domains and paths of all cookies.
+///
+/// ```
+/// let jar = nescookie::open("tests/cookies.txt").unwrap();
+/// assert!(nescookie::estimate_bytes(&jar) > 0);
+/// ```
+pub fn estimate_bytes(jar: &CookieJar) -> usize {
+    jar.iter()
+        .map(|c| {
+            c.name().len()
+                + c.value().len()
+                + c.domain().map_or(0, str::len)
+                + c.path().map_or(0, str::len)
+        })
+        .sum()
+}
+
+/// Returns the cookies in `jar` whose value satisfies `pred`
+///
+/// ```
+/// let jar = nescookie::open("tests/cookies.txt").unwrap();
+/// let found = nescookie::find_by_value(&jar, |v| v.contains("ig3l2"));
+/// assert_eq!(found[0].name(), "PHPSESSID");
+/// ```
+pub fn find_by_value(jar: &CookieJar, pred: impl Fn(&str) -> bool) -> Vec<&Cookie<'static>> {
+    jar.iter().filter(|c| pred(c.value())).collect()
+}
+
+/// Returns a copy of `jar` with every value replaced by `REDACTED`
+///
+/// Names, domains, paths, flags and expirations are kept, so the result can be shared safely.
+///
+/// ```
+/// let jar = nescookie::open("tests/cookies.txt").unwrap();
+/// let redacted = nescookie::redact(&jar);
+/// assert_eq!(redacted.get("p_ab_id").unwrap().value(), "REDACTED");
+/// ```
+pub fn redact(jar: &CookieJar) -> CookieJar {
+    let mut redacted = CookieJar::new();
+    for c in jar.iter() {
+        let mut c = c.clone();
+        c.set_value("REDACTED");
+        redacted.add(c);
+    }
+    redacted
+}
+
+/// Groups the cookies in `jar` into separate jars keyed by their domain
+///
+/// Domains are kept as written, so `.pixiv.net` and `www.pixiv.net` are different keys.
+/// Cookies without a domain are grouped under an empty string.
+///
+/// ```
+/// let jar = nescookie::open("tests/cookies.txt").unwrap();
+/// let jars = nescookie::split_by_domain(&jar);
+/// assert_eq!(jars[".pixiv.net"].iter().count(), 4);
+/// ```
+pub fn split_by_domain(jar: &CookieJar) -> BTreeMap<String, CookieJar> {
+    let mut jars = BTreeMap::new();
+    for c in jar.iter() {
+        jars.entry(c.domain().unwrap_or_default().to_owned())
+            .or_insert_with(CookieJar::new)
+            .add(c.clone());
+    }
+    jars
+}
+
+/// Returns the name and value of every cookie in `jar`
+///
+/// ```
+/// let jar = nescookie::open("tests/cookies.txt").unwrap();
+/// let pairs = nescookie::pairs(&jar);
+/// assert_eq!(pairs.len(), 6);
+/// ```
+pub fn pairs(jar: &CookieJar) -> Vec<(String, String)> {
+    jar.iter()
+        .map(|c| (c.name().to_owned(), c.value().to_owned()))
+        .collect()
+}
+
+/// Returns the cookies in `jar` that are marked HttpOnly
+///
+/// ```
+/// let jar = nescookie::open("tests/cookies.txt").unwrap();
+/// let names: Vec<_> = nescookie::http_only_cookies(&jar).map(|c| c.name()).collect();
+/// assert_eq!(names, ["PHPSESSID"]);
+/// ```
+pub fn http_only_cookies(jar: &CookieJar) -> impl Iterator<Item = &Cookie<'static>> {
+    jar.iter().filter(|c| c.http_only() == Some(true))
+}
+
+/// Splits the cookies in `jar` into the secure ones and the ones that would also be sent over plain HTTP
+///
+/// ```
+/// let jar = nescookie::open("tests/cookies.txt").unwrap();
+/// let (secure, insecure) = nescookie::partition_secure(&jar);
+/// assert_eq!(secure.len(), 6);
+/// assert!(insecure.is_empty());
+/// ```
+pub fn partition_secure(jar: &CookieJar) -> (Vec<&Cookie<'static>>, Vec<&Cookie<'static>>) {
+    jar.iter().partition(|c| c.secure() == Some(true))
+}
+
+/// Returns the cookies in `jar` sorted by how soon they expire
+///
+/// Session cookies come last.
+///
+/// ```
+/// let jar = nescookie::open("tests/cookies.txt").unwrap();
+/// let sorted = nescookie::sorted_by_expiry(&jar);
+/// assert_eq!(sorted[0].name(), "PHPSESSID");
+/// ```
+pub fn sorted_by_expiry(jar: &CookieJar) -> Vec<&Cookie<'static>> {
+    let mut cookies: Vec<_> = jar.iter().collect();
+    cookies.sort_by_key(|c| {
+        let exp = c.expires_datetime();
+        (exp.is_none(), exp)
+    });
+    cookies
+}
+
+/// Returns the cookies in `jar` that have not expired at `now` but will within `within`
+///
+/// Session cookies are never included. If `now + within` is out of the supported range of years,
+/// every cookie expiring after `now` is included.
+///
+/// ```
+/// use nescookie::OffsetDateTime;
+/// use std::time::Duration;
+///
+/// let jar = nescookie::open("tests/cookies.txt").unwrap();
+/// let now = OffsetDateTime::from_unix_timestamp(1689731000);
+/// let expiring = nescookie::expiring_within(&jar, Duration::from_secs(3600), now);
+/// assert_eq!(expiring.len(), 2);
+/// ```
+pub fn expiring_within(
+    jar: &CookieJar,
+    within: Duration,
+    now: OffsetDateTime,
+) -> Vec<&Cookie<'static>> {
+    let until = checked_add(now, within);
+    jar.iter()
+        .filter(|c| match c.expires_datetime() {
+            Some(exp) => exp > now && until.is_none_or(|until| exp <= until),
+            None => false,
+        })
+        .collect()
+}
+
+/// Replaces the expiration of the cookie named `name` with `domain` by `new`
+///
+/// Returns whether such a cookie was found.
+///
+/// ```
+/// use nescookie::OffsetDateTime;
+///
+/// let mut jar = nescookie::open("tests/cookies.txt").unwrap();
+/// let new = OffsetDateTime::from_unix_timestamp(4102444800);
+/// assert!(nescookie::set_expiry(&mut jar, "p_ab_id", ".pixiv.net", new));
+/// assert_eq!(jar.get("p_ab_id").unwrap().expires_datetime(), Some(new));
+/// ```
+pub fn set_expiry(jar: &mut CookieJar, name: &str, domain: &str, new: OffsetDateTime) -> bool {
+    let mut cookie = match jar.get(name) {
+        Some(c) if matches!(c.domain(), Some(d) if d.eq_ignore_ascii_case(domain)) => c.clone(),
+        _ => return false,
+    };
+    jar.remove(cookie.clone());
+    cookie.set_expires(new);
+    jar.add(cookie);
+    true
+}
+
+/// Replaces the value of the cookie named `name` with `domain` by `value`
+///
+/// The other attributes of the cookie are kept. Returns whether such a cookie was found.
+///
+/// ```
+/// let mut jar = nescookie::open("tests/cookies.txt").unwrap();
+/// assert!(nescookie::set_value(&mut jar, "PHPSESSID", ".pixiv.net", "rotated"));
+/// assert_eq!(jar.get("PHPSESSID").unwrap().value(), "rotated");
+/// ```
+pub fn set_value(jar: &mut CookieJar, name: &str, domain: &str, value: &str) -> bool {
+    let mut cookie = match jar.get(name) {
+        Some(c) if matches!(c.domain(), Some(d) if d.eq_ignore_ascii_case(domain)) => c.clone(),
+        _ => return false,
+    };
+    jar.remove(cookie.clone());
+    cookie.set_value(value.to_owned());
+    jar.add(cookie);
+    true
+}
+
+/// Adds the pairs of a `Cookie` request header to `jar` as cookies scoped to `domain` and `path`
+///
+/// A leading `Cookie:` is optional. Pairs without `=` are skipped.
+///
+/// ```
+/// let mut jar = nescookie::CookieJar::new();
+/// nescookie::add_cookie_header(&mut jar, "Cookie: a=b; c=d", ".pixiv.net", "/");
+/// assert_eq!(jar.get("c").unwrap().domain(), Some(".pixiv.net"));
+/// ```
+pub fn add_cookie_header(jar: &mut CookieJar, header: &str, domain: &str, path: &str) {
+    let header = header.trim();
+    let pairs = match header.split_once(':') {
+        Some((name, pairs)) if name.eq_ignore_ascii_case("cookie") => pairs,
+        _ => header,
+    };
+    for (name, value) in pairs.split(';').filter_map(|p| p.split_once('=')) {
+        let cookie = Cookie::build(name.trim().to_owned(), value.trim().to_owned())
+            .domain(domain.to_owned())
+            .path(path.to_owned())
+            .finish();
+        jar.add(cookie);
+    }
+}
+
+/// Hashes the cookies in `jar` for change detection
+///
+/// The domain, path, name, value, flags and expiration of every cookie are hashed in sorted order,
+/// so the result does not depend on the order cookies were added in.
+/// The hash is 64-bit FNV-1a, so it is stable across runs, platforms and Rust versions.
+/// Every field is written as a byte telling whether it is set, followed by its value:
+/// strings are prefixed with their length as a little-endian `u64`, flags are a byte of `0` or `1`
+/// and the expiration is a little-endian `i64` unix timestamp.
+///
+/// ```
+/// let jar = nescookie::open("tests/cookies.txt").unwrap();
+/// let mut changed = jar.clone();
+/// assert_eq!(nescookie::fingerprint(&jar), nescookie::fingerprint(&changed));
+/// nescookie::set_value(&mut changed, "p_ab_id", ".pixiv.net", "8");
+/// assert_ne!(nescookie::fingerprint(&jar), nescookie::fingerprint(&changed));
+/// ```
+pub fn fingerprint(jar: &CookieJar) -> u64 {
+    let mut cookies: Vec<_> = jar
+        .iter()
+        .map(|c| {
+            (
+                c.domain(),
+                c.path(),
+                c.name(),
+                c.value(),
+                c.secure(),
+                c.http_only(),
+                c.expires_datetime().map(|t| t.unix_timestamp()),
+            )
+        })
+        .collect();
+    cookies.sort_unstable();
+    let mut hasher = Fnv1a::default();
+    for (domain, path, name, value, secure, http_only, expires) in cookies {
+        for s in [domain, path, Some(name), Some(value)] {
+            hasher.write_option(s.map(|s| {
+                let mut bytes = (s.len() as u64).to_le_bytes().to_vec();
+                bytes.extend_from_slice(s.as_bytes());
+                bytes
+            }));
+        }
+        for flag in [secure, http_only] {
+            hasher.write_option(flag.map(|flag| vec![u8::from(flag)]));
+        }
+        hasher.write_option(expires.map(|t| t.to_le_bytes().to_vec()));
+    }
+    hasher.0
+}
+
+/// The 64-bit FNV-1a hash
+struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Self(0xcbf29ce484222325)
+    }
+}
+
+impl Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = (self.0 ^ u64::from(b)).wrapping_mul(0x100000001b3);
+        }
+    }
+    fn write_option(&mut self, bytes: Option<Vec<u8>>) {
+        match bytes {
+            Some(bytes) => {
+                self.write(&[1]);
+                self.write(&bytes);
+            }
+            None => self.write(&[0]),
+        }
+    }
+}
+
+/// Returns whether any cookie in `jar` applies to `domain`
+///
+/// A cookie domain with a leading dot also applies to its subdomains,
+/// otherwise the domain has to match exactly.
+///
+/// ```
+/// let jar = nescookie::open("tests/cookies.txt").unwrap();
+/// assert!(nescookie::has_domain(&jar, "www.pixiv.net"));
+/// ```
+pub fn has_domain(jar: &CookieJar, domain: &str) -> bool {
+    jar.iter()
+        .any(|c| matches!(c.domain(), Some(d) if domain_matches(d, domain)))
+}
+
+/// Checks whether a cookie with `cookie_domain` applies to `host`
+pub(crate) fn domain_matches(cookie_domain: &str, host: &str) -> bool {
+    match cookie_domain.strip_prefix('.') {
+        Some(suffix) => {
+            host.eq_ignore_ascii_case(suffix)
+                || (host.len() > suffix.len()
+                    && host.as_bytes()[host.len() - suffix.len() - 1] == b'.'
+                    && host[host.len() - suffix.len()..].eq_ignore_ascii_case(suffix))
+        }
+        None => host.eq_ignore_ascii_case(cookie_domain),
+    }
+}
@@ -1,22 +1,443 @@
 #![allow(clippy::tabs_in_doc_comments)]
 
+#[cfg(any(feature = "zip", feature = "tar", feature = "zstd"))]
+mod archive;
+mod binary;
+#[cfg(feature = "chrono")]
+mod chrono;
+#[cfg(feature = "cookie_store")]
+mod cookie_store;
+#[cfg(feature = "encoding")]
+mod encoding;
 pub mod error;
+mod jar;
+mod lines;
+#[cfg(feature = "miette")]
+mod miette;
+#[cfg(feature = "mmap")]
+mod mmap;
+mod netscape;
+mod record;
+#[cfg(feature = "rusqlite")]
+mod sqlite;
+#[cfg(feature = "futures")]
+mod stream;
+#[cfg(feature = "url")]
+mod url;
+mod writer;
 
+#[cfg(feature = "chrono")]
+pub use crate::chrono::{expires_chrono, from_chrono, to_chrono};
+#[cfg(feature = "cookie_store")]
+pub use crate::cookie_store::from_cookie_store;
 use crate::error::Error;
-pub use cookie::{Cookie, CookieJar};
+#[cfg(feature = "miette")]
+pub use crate::miette::SourceError;
+pub use binary::{from_bytes, to_bytes};
+pub use cookie::{Cookie, CookieJar, SameSite};
 use error::ParseError;
+pub use jar::{
+    add_cookie_header, debug_dump, estimate_bytes, expiring_within, find_by_value, fingerprint,
+    has_domain, http_only_cookies, min_expiry, pairs, partition_secure, prune_expired, redact,
+    seconds_until_expiry, set_expiry, set_value, sorted_by_expiry, split_by_domain,
+};
+pub use lines::CookieLines;
+pub use netscape::NetscapeCookies;
+pub use record::{name_collisions, rows, to_record, Record};
+#[cfg(feature = "rusqlite")]
+pub use sqlite::to_sqlite;
 use std::{
-    fs::File,
-    io::{BufRead, BufReader},
-    path::Path,
+    borrow::Cow,
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    io::{self, BufRead},
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
 };
+#[cfg(feature = "futures")]
+pub use stream::parse_stream;
+use time::Format;
 pub use time::OffsetDateTime;
+#[cfg(feature = "url")]
+pub use url::cookies_for_url;
+pub use writer::{Case, CookieJarWriter, Dialect};
+
+const MIN_TIMESTAMP: i64 = -62135596800; // 0001-01-01T00:00:00Z
+const MAX_TIMESTAMP: i64 = 253402300799; // 9999-12-31T23:59:59Z
+
+/// Converts a unix timestamp to a datetime, returning `None` if it is out of the range of years 1 to 9999
+pub(crate) fn timestamp(exp: i64) -> Option<OffsetDateTime> {
+    (MIN_TIMESTAMP..=MAX_TIMESTAMP)
+        .contains(&exp)
+        .then(|| OffsetDateTime::from_unix_timestamp(exp))
+}
+
+/// Converts a unix timestamp in nanoseconds to a datetime, returning `None` if it is out of the range of [`timestamp`]
+pub(crate) fn timestamp_nanos(nanos: i128) -> Option<OffsetDateTime> {
+    let secs = i64::try_from(nanos.div_euclid(1_000_000_000)).ok()?;
+    timestamp(secs).map(|_| OffsetDateTime::from_unix_timestamp_nanos(nanos))
+}
+
+/// Adds `d` to `t`, returning `None` if the result is out of the range of [`timestamp`]
+pub(crate) fn checked_add(t: OffsetDateTime, d: Duration) -> Option<OffsetDateTime> {
+    let nanos = i128::try_from(d.as_nanos()).ok()?;
+    timestamp_nanos(t.unix_timestamp_nanos().checked_add(nanos)?)
+}
+
+/// Checks whether `name` is a token as defined by RFC 6265
+fn is_token(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_graphic() && !b"()<>@,;:\\\"/[]?={}".contains(&b))
+}
+
+/// A line handed to [`CookieJarBuilder::parse_into`]
+///
+/// A line may end with its line break, which is not part of its text.
+trait SourceLine {
+    /// Returns the text of the line without its line break
+    fn text(&self) -> &str;
+    /// Returns the length of the line in its source, which is longer than its text if it was truncated
+    fn source_len(&self) -> usize {
+        self.text().len()
+    }
+    /// Returns the number of bytes the line took in its source, including its line break
+    fn bytes(&self) -> usize;
+}
+
+impl<T: AsRef<str>> SourceLine for T {
+    fn text(&self) -> &str {
+        let line = self.as_ref();
+        match line.strip_suffix('\n') {
+            Some(line) => line.strip_suffix('\r').unwrap_or(line),
+            None => line,
+        }
+    }
+    fn bytes(&self) -> usize {
+        self.as_ref().len()
+    }
+}
+
+/// A line read by [`read_line`], keeping only the start of a line that is too long
+struct ReadLine {
+    text: String,
+    len: usize,
+    read: usize,
+}
+
+impl SourceLine for ReadLine {
+    fn text(&self) -> &str {
+        &self.text
+    }
+    fn source_len(&self) -> usize {
+        self.len
+    }
+    fn bytes(&self) -> usize {
+        self.read
+    }
+}
+
+/// Reads a line of `buf` without its line break, keeping at most `max + 1` bytes of it
+///
+/// Returns the line with its full length and the number of bytes read, or `None` at the end of `buf`.
+fn read_line(buf: &mut impl BufRead, max: usize) -> io::Result<Option<ReadLine>> {
+    let mut line = Vec::new();
+    let (mut len, mut read, mut newline, mut last) = (0, 0, false, None);
+    while !newline {
+        let available = match buf.fill_buf() {
+            Ok(available) => available,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        if available.is_empty() {
+            break;
+        }
+        let part = match available.iter().position(|&b| b == b'\n') {
+            Some(i) => {
+                newline = true;
+                &available[..i]
+            }
+            None => available,
+        };
+        let keep = max
+            .saturating_add(1)
+            .saturating_sub(line.len())
+            .min(part.len());
+        line.extend_from_slice(&part[..keep]);
+        len += part.len();
+        last = part.last().copied().or(last);
+        let used = part.len() + usize::from(newline);
+        buf.consume(used);
+        read += used;
+    }
+    if read == 0 {
+        return Ok(None);
+    }
+    // a line break may be preceded by a carriage return, like in `str::lines`
+    if newline && last == Some(b'\r') {
+        len -= 1;
+        // the carriage return is not kept if the line was truncated before it
+        if line.len() > len {
+            line.pop();
+        }
+    }
+    let text = if len > max {
+        String::from_utf8_lossy(&line).into_owned()
+    } else {
+        String::from_utf8(line).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+    };
+    Ok(Some(ReadLine { text, len, read }))
+}
+
+/// Removes the byte order mark from the start of a source, where `i` is the 0-based index of `line`
+fn strip_bom(line: &str, i: usize) -> &str {
+    match i {
+        0 => line.strip_prefix('\u{feff}').unwrap_or(line),
+        _ => line,
+    }
+}
+
+/// Splits `line` on `delimiter`, treating fields wrapped in double quotes as a whole
+///
+/// Inside a quoted field `""` stands for a literal quote.
+/// A field with an unterminated quote is kept as is.
+fn split_quoted(line: &str, delimiter: char) -> Vec<Cow<'_, str>> {
+    let mut fields = Vec::new();
+    let mut rest = line;
+    loop {
+        if let Some(quoted) = rest.strip_prefix('"') {
+            let mut field = String::new();
+            let mut chars = quoted.char_indices().peekable();
+            let mut end = None;
+            while let Some((i, c)) = chars.next() {
+                match c {
+                    '"' if matches!(chars.peek(), Some((_, '"'))) => {
+                        field.push('"');
+                        chars.next();
+                    }
+                    '"' => {
+                        end = Some(i + 1);
+                        break;
+                    }
+                    c => field.push(c),
+                }
+            }
+            if let Some(end) = end {
+                let (tail, next) = match quoted[end..].split_once(delimiter) {
+                    Some((tail, next)) => (tail, Some(next)),
+                    None => (&quoted[end..], None),
+                };
+                field.push_str(tail);
+                fields.push(Cow::Owned(field));
+                match next {
+                    Some(next) => rest = next,
+                    None => return fields,
+                }
+                continue;
+            }
+        }
+        match rest.split_once(delimiter) {
+            Some((field, next)) => {
+                fields.push(Cow::Borrowed(field));
+                rest = next;
+            }
+            None => {
+                fields.push(Cow::Borrowed(rest));
+                return fields;
+            }
+        }
+    }
+}
+
+/// How [`CookieJarBuilder`] handles cookies sharing a name within a single source
+///
+/// A `# Netscape HTTP Cookie File` header starts a new source, so that
+/// concatenated files are handled like separate ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicatePolicy {
+    /// The later cookie replaces the earlier one
+    #[default]
+    Overwrite,
+    /// The earlier cookie is kept and later ones are ignored
+    KeepFirst,
+    /// Parsing fails with `ParseError::DuplicateName`
+    Error,
+}
+
+/// What [`CookieJarBuilder`] does when a line fails to parse
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ErrorMode {
+    /// Parsing stops with the error
+    #[default]
+    FailFast,
+    /// The line is skipped
+    Skip,
+    /// The line is skipped and its error is kept in [`CookieJarBuilder::warnings`]
+    Collect,
+}
+
+/// Which spellings [`CookieJarBuilder`] accepts for boolean fields
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BoolStyle {
+    /// Only `TRUE` and `FALSE`
+    #[default]
+    Canonical,
+    /// `TRUE`/`FALSE` in any case, `1`/`0` and `yes`/`no`
+    Lenient,
+}
+
+impl BoolStyle {
+    fn parse(self, s: &str) -> Option<bool> {
+        match (self, s) {
+            (_, "TRUE") => Some(true),
+            (_, "FALSE") => Some(false),
+            (Self::Canonical, _) => None,
+            (Self::Lenient, "1") => Some(true),
+            (Self::Lenient, "0") => Some(false),
+            (Self::Lenient, s)
+                if s.eq_ignore_ascii_case("true") || s.eq_ignore_ascii_case("yes") =>
+            {
+                Some(true)
+            }
+            (Self::Lenient, s)
+                if s.eq_ignore_ascii_case("false") || s.eq_ignore_ascii_case("no") =>
+            {
+                Some(false)
+            }
+            (Self::Lenient, _) => None,
+        }
+    }
+}
+
+/// Statistics of a single parse, see [`CookieJarBuilder::metrics`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseMetrics {
+    /// The number of lines, including comments and blank lines
+    pub lines_seen: usize,
+    /// The number of cookies added to the jar
+    pub cookies_added: usize,
+    /// The length of the lines in bytes, including their line breaks
+    pub bytes_read: usize,
+    /// The time spent parsing
+    pub elapsed: Duration,
+}
 
 /// A netscape cookie parser
 /// allowing generating a new [`CookieJar`](cookie::CookieJar) or writing to an exist one.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct CookieJarBuilder {
     jar: CookieJar,
+    allow_missing_value: bool,
+    now: Option<OffsetDateTime>,
+    duplicate_policy: DuplicatePolicy,
+    tolerant_columns: bool,
+    sources: HashMap<String, PathBuf>,
+    rename: Option<Hook<Rename>>,
+    head: Option<usize>,
+    bare: bool,
+    validate_names: bool,
+    force_secure: Option<bool>,
+    force_http_only: Option<bool>,
+    force_path: Option<String>,
+    prefer_latest_expiry: bool,
+    keep_trailing_dot: bool,
+    csv_mode: bool,
+    sniff: bool,
+    disallow_comments: bool,
+    max_line_len: Option<usize>,
+    tolerate: Option<Hook<Tolerate>>,
+    warnings: Vec<(usize, ParseError)>,
+    collapse_tabs: bool,
+    metrics: Option<Hook<Metrics>>,
+    extended_comments: bool,
+    metadata: BTreeMap<String, String>,
+    missing_expiry_is_session: bool,
+    occurrences: HashMap<(String, String), usize>,
+    default_max_age: Option<Duration>,
+    flag_whitespace_lines: bool,
+    max_cookies_per_domain: Option<usize>,
+    bool_style: BoolStyle,
+    keep_raw: bool,
+    raw_lines: Vec<(String, RawLine)>,
+    parse_disabled: bool,
+    domain_from_flag: bool,
+    error_mode: ErrorMode,
+    allow_empty_name: bool,
+    #[cfg(feature = "percent-encoding")]
+    decode_values: bool,
+    delimiter: Option<char>,
+    same_site: Option<SameSite>,
+    path_prefix: Option<String>,
+    preserve_comments: bool,
+    repair_missing_path: bool,
+    chunk_position: Position,
+}
+
+/// The progress of a parse, which is carried across calls to [`CookieJarBuilder::parse_chunk`]
+#[derive(Debug, Default, Clone)]
+struct Position {
+    /// The number of lines parsed
+    lines: usize,
+    /// The number of cookies added
+    added: usize,
+    /// The names of cookies seen since the last header
+    seen: HashSet<String>,
+    /// Whether the format has been checked by [`sniff`](CookieJarBuilder::sniff)
+    sniffed: bool,
+    /// The number of cookies for each domain, only kept when they are limited
+    domain_counts: HashMap<Option<String>, usize>,
+}
+
+/// What a line kept for [`CookieJarBuilder::write`] was read as
+#[derive(Debug, Clone)]
+enum RawLine {
+    /// A comment, a blank line or a line whose cookie was filtered out
+    Other,
+    /// A line that failed to parse, whose error was recovered from
+    Failed,
+    /// A line whose cookie was added, and whether the line still reads as that cookie
+    Cookie(Cookie<'static>, bool),
+}
+
+/// What became of a line handed to [`CookieJarBuilder::parse_source_line`]
+enum LineOutcome {
+    /// The line holds no cookie, or its cookie was filtered out
+    Skipped,
+    /// The line failed to parse and its error was recovered from
+    Failed,
+    /// The line was not parsed since the limit of [`parse_head`] was reached
+    Stop,
+    Cookie(Cookie<'static>),
+}
+
+/// A user supplied callback stored in [`CookieJarBuilder`]
+///
+/// Clones of a builder share the same callback.
+struct Hook<F: ?Sized>(Arc<Mutex<F>>);
+
+type Rename = dyn FnMut(&str) -> String + Send;
+type Tolerate = dyn Fn(&ParseError) -> bool + Send;
+type Metrics = Option<Box<dyn FnOnce(ParseMetrics) + Send>>;
+
+impl<F: ?Sized> Hook<F> {
+    fn lock(&self) -> MutexGuard<'_, F> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl<F: ?Sized> Clone for Hook<F> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+impl<F: ?Sized> std::fmt::Debug for Hook<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Hook")
+    }
 }
 
 impl CookieJarBuilder {
@@ -24,93 +445,1413 @@ impl CookieJarBuilder {
     /// ```
     /// use nescookie::CookieJarBuilder;
     ///
-    /// let jar = CookieJarBuilder::new().finish();
+    /// let jar = CookieJarBuilder::new().finish();
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Creates a new `CookieJarBuilder` from a [`CookieJar`](cookie::CookieJar)
+    /// parsed cookies will be added to it
+    pub fn with_jar(jar: CookieJar) -> Self {
+        Self {
+            jar,
+            ..Self::default()
+        }
+    }
+    /// Opens a file with `path` and parses it as cookies
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let jar = CookieJarBuilder::new().open("tests/cookies.txt").unwrap().finish();
+    /// ```
+    pub fn open(self, path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let s = fs::read_to_string(path).map_err(Error::open(path))?;
+        self.parse_source(&s, Some(path))
+    }
+    /// Opens files with `paths` in order and parses them as cookies
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let jar = CookieJarBuilder::new()
+    ///     .open_many(["tests/cookies.txt", "benches/cookies.txt"])
+    ///     .unwrap()
+    ///     .finish();
+    /// ```
+    pub fn open_many(
+        mut self,
+        paths: impl IntoIterator<Item = impl AsRef<Path>>,
+    ) -> Result<Self, Error> {
+        for path in paths {
+            self = self.open(path)?;
+        }
+        Ok(self)
+    }
+    /// Opens every `.txt` file directly inside `dir` and parses them as cookies
+    ///
+    /// Files are read in the order of their names. Subdirectories and files with other
+    /// extensions are skipped.
+    ///
+    /// ```no_run
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let jar = CookieJarBuilder::new().open_dir("cookies").unwrap().finish();
+    /// ```
+    pub fn open_dir(self, dir: impl AsRef<Path>) -> Result<Self, Error> {
+        let mut paths = Vec::new();
+        let dir = dir.as_ref();
+        for entry in fs::read_dir(dir).map_err(Error::open(dir))? {
+            let entry = entry.map_err(Error::open(dir))?;
+            let path = entry.path();
+            let file_type = entry.file_type().map_err(Error::open(&path))?;
+            if file_type.is_file() && path.extension().is_some_and(|e| e == "txt") {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        self.open_many(paths)
+    }
+    /// Parses cookies from something that implements [`BufRead`](std::io::BufRead)
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    /// use std::io::Cursor;
+    ///
+    /// let buf = Cursor::new(b".pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7\n");
+    /// let jar = CookieJarBuilder::new().parse_buffer(buf).unwrap().finish();
+    /// ```
+    pub fn parse_buffer(self, buf: impl BufRead) -> Result<Self, Error> {
+        self.parse_buffer_counted(buf).map(|(builder, _)| builder)
+    }
+    /// Parses cookies from something that implements [`BufRead`](std::io::BufRead),
+    /// also returning the number of bytes read from it
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    /// use std::io::Cursor;
+    ///
+    /// let content = b".pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7\n";
+    /// let (builder, read) = CookieJarBuilder::new()
+    ///     .parse_buffer_counted(Cursor::new(content))
+    ///     .unwrap();
+    /// assert_eq!(read, content.len());
+    /// ```
+    pub fn parse_buffer_counted(mut self, mut buf: impl BufRead) -> Result<(Self, usize), Error> {
+        let max = match self.max_line_len {
+            Some(max) => max,
+            None => {
+                let mut s = String::new();
+                let read = buf.read_to_string(&mut s)?;
+                return Ok((self.parse(&s)?, read));
+            }
+        };
+        // only keep the start of lines that are too long
+        let (mut lines, mut read) = (Vec::new(), 0);
+        while let Some(line) = read_line(&mut buf, max)? {
+            read += line.read;
+            lines.push(line);
+        }
+        self.parse_into(lines, None, None, &mut Position::default())?;
+        Ok((self, read))
+    }
+    /// Parses cookies from UTF-8 encoded bytes
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let content = b".pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7\n";
+    /// let jar = CookieJarBuilder::new().parse_bytes(content).unwrap().finish();
+    /// ```
+    pub fn parse_bytes(self, bytes: &[u8]) -> Result<Self, Error> {
+        let s = std::str::from_utf8(bytes)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.parse(s)
+    }
+    /// Parses cookies from an str
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let content = ".pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7\n";
+    /// let jar = CookieJarBuilder::new().parse(content).unwrap().finish();
+    /// ```
+    pub fn parse(mut self, s: &str) -> Result<Self, Error> {
+        self.parse_into(
+            s.split_inclusive('\n'),
+            None,
+            None,
+            &mut Position::default(),
+        )?;
+        Ok(self)
+    }
+    /// Parses cookies from an str, skipping invalid lines and returning their errors
+    ///
+    /// If every line parses, no errors are returned and the result is the same as [`parse`](Self::parse).
+    /// Otherwise the builder is left with every cookie that could be parsed, together with
+    /// the errors of the other lines. Errors handled by [`on_error`](Self::on_error) or
+    /// [`tolerate`](Self::tolerate) are not returned, and other errors, such as an invalid
+    /// [`delimiter`](Self::delimiter), are returned as is.
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let content = ".pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7\nnot a cookie\n";
+    /// let (builder, errors) = CookieJarBuilder::new().parse_or_lenient(content).unwrap();
+    /// assert_eq!(builder.finish().iter().count(), 1);
+    /// assert_eq!(errors.len(), 1);
+    /// ```
+    pub fn parse_or_lenient(mut self, s: &str) -> Result<(Self, Vec<ParseError>), Error> {
+        let mut errors = Vec::new();
+        self.parse_into(
+            s.split_inclusive('\n'),
+            None,
+            Some(&mut errors),
+            &mut Position::default(),
+        )?;
+        Ok((self, errors))
+    }
+    /// Parses cookies from lines that have already been split
+    ///
+    /// Line numbers in errors count every item of `lines`, starting from 1.
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let lines = vec![
+    ///     "# Netscape HTTP Cookie File",
+    ///     ".pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7",
+    /// ];
+    /// let jar = CookieJarBuilder::new().parse_lines_iter(lines).unwrap().finish();
+    /// assert_eq!(jar.get("p_ab_id").unwrap().value(), "7");
+    /// ```
+    pub fn parse_lines_iter(
+        mut self,
+        lines: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> Result<Self, Error> {
+        self.parse_into(lines, None, None, &mut Position::default())?;
+        Ok(self)
+    }
+    /// Returns the file the cookie named `name` was loaded from by [`open`](Self::open)
+    /// or [`open_many`](Self::open_many)
+    ///
+    /// Returns `None` if the cookie was parsed from any other source.
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    /// use std::path::Path;
+    ///
+    /// let builder = CookieJarBuilder::new().open("tests/cookies.txt").unwrap();
+    /// assert_eq!(builder.source_of("p_ab_id"), Some(Path::new("tests/cookies.txt")));
+    /// ```
+    pub fn source_of(&self, name: &str) -> Option<&Path> {
+        self.sources.get(name).map(|p| p.as_path())
+    }
+    /// Sets whether a line missing its value field is parsed as an empty value
+    ///
+    /// A line ending with a tab after the name always yields an empty value.
+    /// By default, a line ending right after the name is rejected with `TooFewFileds`.
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let content = ".pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id\n";
+    /// let jar = CookieJarBuilder::new()
+    ///     .allow_missing_value(true)
+    ///     .parse(content)
+    ///     .unwrap()
+    ///     .finish();
+    /// assert_eq!(jar.get("p_ab_id").unwrap().value(), "");
+    /// ```
+    pub fn allow_missing_value(mut self, allow: bool) -> Self {
+        self.allow_missing_value = allow;
+        self
+    }
+    /// Skips cookies that have expired at `now`
+    ///
+    /// Session cookies are always kept.
+    ///
+    /// ```
+    /// use nescookie::{CookieJarBuilder, OffsetDateTime};
+    ///
+    /// let jar = CookieJarBuilder::new()
+    ///     .skip_expired(OffsetDateTime::from_unix_timestamp(1700000000))
+    ///     .open("tests/cookies.txt")
+    ///     .unwrap()
+    ///     .finish();
+    /// assert!(jar.get("PHPSESSID").is_none());
+    /// ```
+    pub fn skip_expired(mut self, now: OffsetDateTime) -> Self {
+        self.now = Some(now);
+        self
+    }
+    /// Sets how cookies sharing a name within one file or str are handled
+    ///
+    /// Defaults to [`DuplicatePolicy::Overwrite`].
+    ///
+    /// ```
+    /// use nescookie::{CookieJarBuilder, DuplicatePolicy};
+    ///
+    /// let content = ".pixiv.net	TRUE	/	TRUE	0	a	1\nwww.pixiv.net	FALSE	/	TRUE	0	a	2\n";
+    /// let jar = CookieJarBuilder::new()
+    ///     .duplicate_policy(DuplicatePolicy::KeepFirst)
+    ///     .parse(content)
+    ///     .unwrap()
+    ///     .finish();
+    /// assert_eq!(jar.get("a").unwrap().value(), "1");
+    /// ```
+    pub fn duplicate_policy(mut self, policy: DuplicatePolicy) -> Self {
+        self.duplicate_policy = policy;
+        self
+    }
+    /// Transforms the name of every parsed cookie with `f` before it is added
+    ///
+    /// Clones of the builder share `f`.
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let jar = CookieJarBuilder::new()
+    ///     .rename(|name| format!("p1_{}", name))
+    ///     .open("tests/cookies.txt")
+    ///     .unwrap()
+    ///     .finish();
+    /// assert!(jar.get("p1_PHPSESSID").is_some());
+    /// ```
+    pub fn rename(mut self, f: impl FnMut(&str) -> String + Send + 'static) -> Self {
+        self.rename = Some(Hook(Arc::new(Mutex::new(f))));
+        self
+    }
+    /// Sets whether swapped secure and expiration columns are detected and fixed
+    ///
+    /// When set, a line whose secure column holds a number and whose expiration column
+    /// holds `TRUE` or `FALSE` is parsed as if the two columns were in order.
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let content = ".pixiv.net	TRUE	/	1784339332	TRUE	p_ab_id	7\n";
+    /// let jar = CookieJarBuilder::new()
+    ///     .tolerant_columns(true)
+    ///     .parse(content)
+    ///     .unwrap()
+    ///     .finish();
+    /// assert_eq!(jar.get("p_ab_id").unwrap().secure(), Some(true));
+    /// ```
+    pub fn tolerant_columns(mut self, tolerant: bool) -> Self {
+        self.tolerant_columns = tolerant;
+        self
+    }
+    /// Sets whether cookies are built without their domain, path and secure flag
+    ///
+    /// This is useful when the jar is only used as a map from names to values.
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let jar = CookieJarBuilder::new()
+    ///     .bare(true)
+    ///     .open("tests/cookies.txt")
+    ///     .unwrap()
+    ///     .finish();
+    /// assert_eq!(jar.get("p_ab_id").unwrap().domain(), None);
+    /// ```
+    pub fn bare(mut self, bare: bool) -> Self {
+        self.bare = bare;
+        self
+    }
+    /// Sets whether cookie names are checked to be RFC 6265 tokens
+    ///
+    /// When set, names that are empty or contain control characters, whitespace,
+    /// separators or non-ASCII characters are rejected with `ParseError::InvalidName`.
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let content = ".pixiv.net	TRUE	/	TRUE	1784339332	p ab id	7\n";
+    /// assert!(CookieJarBuilder::new().validate_names(true).parse(content).is_err());
+    /// ```
+    pub fn validate_names(mut self, validate: bool) -> Self {
+        self.validate_names = validate;
+        self
+    }
+    /// Overrides the secure flag of every parsed cookie with `secure`
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let content = "www.pixiv.net	FALSE	/	FALSE	0	yuid_b	FBdWQEY\n";
+    /// let jar = CookieJarBuilder::new()
+    ///     .force_secure(true)
+    ///     .parse(content)
+    ///     .unwrap()
+    ///     .finish();
+    /// assert_eq!(jar.get("yuid_b").unwrap().secure(), Some(true));
+    /// ```
+    pub fn force_secure(mut self, secure: bool) -> Self {
+        self.force_secure = Some(secure);
+        self
+    }
+    /// Overrides the path of every parsed cookie with `path`
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let content = "www.pixiv.net	FALSE	/	FALSE	0	yuid_b	FBdWQEY\n";
+    /// let jar = CookieJarBuilder::new()
+    ///     .force_path("/api")
+    ///     .parse(content)
+    ///     .unwrap()
+    ///     .finish();
+    /// assert_eq!(jar.get("yuid_b").unwrap().path(), Some("/api"));
+    /// ```
+    pub fn force_path(mut self, path: impl Into<String>) -> Self {
+        self.force_path = Some(path.into());
+        self
+    }
+    /// Overrides the HttpOnly flag of every parsed cookie with `http_only`
+    pub fn force_http_only(mut self, http_only: bool) -> Self {
+        self.force_http_only = Some(http_only);
+        self
+    }
+    /// Sets whether a cookie only replaces one of the same name if it expires later
+    ///
+    /// Session cookies are treated as expiring later than any persistent cookie.
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let jar = CookieJarBuilder::new()
+    ///     .prefer_latest_expiry(true)
+    ///     .parse(".pixiv.net	TRUE	/	TRUE	2000000000	a	later\n")
+    ///     .unwrap()
+    ///     .parse(".pixiv.net	TRUE	/	TRUE	1000000000	a	earlier\n")
+    ///     .unwrap()
+    ///     .finish();
+    /// assert_eq!(jar.get("a").unwrap().value(), "later");
+    /// ```
+    pub fn prefer_latest_expiry(mut self, prefer: bool) -> Self {
+        self.prefer_latest_expiry = prefer;
+        self
+    }
+    /// Parses complete lines of `chunk` and returns the trailing incomplete line
+    ///
+    /// The returned tail should be prepended to the next chunk.
+    /// Feed the final tail with [`finish_chunks`](Self::finish_chunks) once the input ends.
+    /// Line numbers in errors and duplicate names are tracked across chunks.
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let mut builder = CookieJarBuilder::new();
+    /// let tail = builder.parse_chunk(".pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7\n.pixiv").unwrap();
+    /// assert_eq!(tail, ".pixiv");
+    /// let tail = builder.parse_chunk(&(tail + ".net	TRUE	/	TRUE	1784339332	p_ab_id_2	9\n")).unwrap();
+    /// assert!(tail.is_empty());
+    /// builder.finish_chunks(&tail).unwrap();
+    /// assert_eq!(builder.finish().iter().count(), 2);
+    /// ```
+    pub fn parse_chunk(&mut self, chunk: &str) -> Result<String, Error> {
+        let (complete, tail) = match chunk.rfind('\n') {
+            Some(i) => chunk.split_at(i + 1),
+            None => ("", chunk),
+        };
+        let mut pos = std::mem::take(&mut self.chunk_position);
+        let result = self.parse_into(complete.split_inclusive('\n'), None, None, &mut pos);
+        self.chunk_position = pos;
+        result.map(|_| tail.to_owned())
+    }
+    /// Parses the final tail returned by [`parse_chunk`](Self::parse_chunk)
+    ///
+    /// The next call to `parse_chunk` starts a new input.
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let mut builder = CookieJarBuilder::new();
+    /// let tail = builder.parse_chunk(".pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7").unwrap();
+    /// builder.finish_chunks(&tail).unwrap();
+    /// assert_eq!(builder.finish().get("p_ab_id").unwrap().value(), "7");
+    /// ```
+    pub fn finish_chunks(&mut self, tail: &str) -> Result<(), Error> {
+        let mut pos = std::mem::take(&mut self.chunk_position);
+        self.parse_into(tail.split_inclusive('\n'), None, None, &mut pos)
+    }
+    /// Sets whether a single trailing dot is removed from domains
+    ///
+    /// Defaults to `true`, so that `pixiv.net.` is stored as `pixiv.net`.
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let content = ".pixiv.net.	TRUE	/	TRUE	1784339332	p_ab_id	7\n";
+    /// let jar = CookieJarBuilder::new().parse(content).unwrap().finish();
+    /// assert_eq!(jar.get("p_ab_id").unwrap().domain(), Some(".pixiv.net"));
+    /// ```
+    pub fn strip_trailing_dot(mut self, strip: bool) -> Self {
+        self.keep_trailing_dot = !strip;
+        self
+    }
+    /// Sets whether fields may be wrapped in double quotes, CSV-style
+    ///
+    /// A quoted field may contain tabs, and `""` inside it stands for a literal quote.
+    /// Defaults to `false`.
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let content = ".pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	\"a	b\"\n";
+    /// let jar = CookieJarBuilder::new().csv_mode(true).parse(content).unwrap().finish();
+    /// assert_eq!(jar.get("p_ab_id").unwrap().value(), "a\tb");
+    /// ```
+    pub fn csv_mode(mut self, csv_mode: bool) -> Self {
+        self.csv_mode = csv_mode;
+        self
+    }
+    /// Sets whether to check that the input looks like a cookies.txt file before parsing it
+    ///
+    /// If the first line that is not a comment does not have at least 6 tab-separated fields,
+    /// parsing fails with `Error::NotNetscapeFormat`.
+    /// This catches HTML or JSON passed by mistake. Defaults to `false`.
+    ///
+    /// ```
+    /// use nescookie::{error::Error, CookieJarBuilder};
+    ///
+    /// let content = "<!DOCTYPE html>\n<html></html>\n";
+    /// let result = CookieJarBuilder::new().sniff(true).parse(content);
+    /// assert!(matches!(result, Err(Error::NotNetscapeFormat)));
+    /// ```
+    pub fn sniff(mut self, sniff: bool) -> Self {
+        self.sniff = sniff;
+        self
+    }
+    /// Sets whether comment lines other than the `# Netscape HTTP Cookie File` header are rejected
+    ///
+    /// Such lines fail with `ParseError::UnexpectedComment`. `#HttpOnly_` lines are still accepted.
+    /// Defaults to `false`.
+    ///
+    /// ```
+    /// use nescookie::{error::{Error, ParseError}, CookieJarBuilder};
+    ///
+    /// let content = "# Netscape HTTP Cookie File\n# edited by hand\n";
+    /// let result = CookieJarBuilder::new().disallow_comments(true).parse(content);
+    /// assert!(matches!(
+    ///     result,
+    ///     Err(Error::ParseError(ParseError::UnexpectedComment { line: 2 }))
+    /// ));
+    /// ```
+    pub fn disallow_comments(mut self, disallow: bool) -> Self {
+        self.disallow_comments = disallow;
+        self
+    }
+    /// Fails with `ParseError::LineTooLong` on lines longer than `len` bytes
+    ///
+    /// When reading from a buffer, at most `len + 1` bytes of each line are kept in memory.
+    ///
+    /// ```
+    /// use nescookie::{error::{Error, ParseError}, CookieJarBuilder};
+    ///
+    /// let content = ".pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7\n";
+    /// let result = CookieJarBuilder::new().max_line_len(16).parse(content);
+    /// assert!(matches!(
+    ///     result,
+    ///     Err(Error::ParseError(ParseError::LineTooLong { line: 1, len: 43 }))
+    /// ));
+    /// ```
+    pub fn max_line_len(mut self, len: usize) -> Self {
+        self.max_line_len = Some(len);
+        self
+    }
+    /// Skips the lines whose error satisfies `predicate` instead of failing
+    ///
+    /// The skipped errors are kept with their 1-based line numbers and can be read with [`warnings`](Self::warnings).
+    ///
+    /// ```
+    /// use nescookie::{error::ParseError, CookieJarBuilder};
+    ///
+    /// let content = ".pixiv.net	TRUE	/	MAYBE	1784339332	p_ab_id	7\n";
+    /// let builder = CookieJarBuilder::new()
+    ///     .tolerate(|e| matches!(e, ParseError::InvaildValue(_)))
+    ///     .parse(content)
+    ///     .unwrap();
+    /// assert_eq!(builder.warnings().len(), 1);
+    /// ```
+    pub fn tolerate(mut self, predicate: impl Fn(&ParseError) -> bool + Send + 'static) -> Self {
+        self.tolerate = Some(Hook(Arc::new(Mutex::new(predicate))));
+        self
+    }
+    /// Returns the warnings collected while parsing, such as errors skipped because of [`tolerate`](Self::tolerate),
+    /// with their 1-based line numbers
+    pub fn warnings(&self) -> &[(usize, ParseError)] {
+        &self.warnings
+    }
+    /// Sets whether runs of tabs are treated as a single separator
+    ///
+    /// This recovers files with doubled tabs, but drops intentionally empty fields
+    /// such as an empty value. It has no effect when fields are separated by another
+    /// [`delimiter`](Self::delimiter). Defaults to `false`.
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let content = ".pixiv.net	TRUE		/	TRUE	1784339332	p_ab_id	7\n";
+    /// let jar = CookieJarBuilder::new().collapse_tabs(true).parse(content).unwrap().finish();
+    /// assert_eq!(jar.get("p_ab_id").unwrap().value(), "7");
+    /// ```
+    pub fn collapse_tabs(mut self, collapse: bool) -> Self {
+        self.collapse_tabs = collapse;
+        self
+    }
+    /// Reports [`ParseMetrics`] to `f` once the next parse succeeds
+    ///
+    /// `f` is called at most once, so later parses are not reported.
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let content = ".pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7\n";
+    /// CookieJarBuilder::new()
+    ///     .metrics(|m| println!("parsed {} cookies in {:?}", m.cookies_added, m.elapsed))
+    ///     .parse(content)
+    ///     .unwrap();
+    /// ```
+    pub fn metrics(mut self, f: impl FnOnce(ParseMetrics) + Send + 'static) -> Self {
+        self.metrics = Some(Hook(Arc::new(Mutex::new(Some(Box::new(f))))));
+        self
+    }
+    /// Sets whether `#@key=value` comment lines are collected as metadata
+    ///
+    /// Such lines are still ignored as cookies. Defaults to `false`.
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let content = "#@source=chrome\n.pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7\n";
+    /// let builder = CookieJarBuilder::new().extended_comments(true).parse(content).unwrap();
+    /// assert_eq!(builder.metadata()["source"], "chrome");
+    /// ```
+    pub fn extended_comments(mut self, extended: bool) -> Self {
+        self.extended_comments = extended;
+        self
+    }
+    /// Returns the metadata collected from `#@key=value` lines, see [`extended_comments`](Self::extended_comments)
+    pub fn metadata(&self) -> &BTreeMap<String, String> {
+        &self.metadata
+    }
+    /// Sets whether lines with 6 fields are read as session cookies without an expiration field
+    ///
+    /// This takes precedence over [`allow_missing_value`](Self::allow_missing_value). Defaults to `false`.
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let content = ".pixiv.net	TRUE	/	TRUE	p_ab_id	7\n";
+    /// let jar = CookieJarBuilder::new()
+    ///     .missing_expiry_is_session(true)
+    ///     .parse(content)
+    ///     .unwrap()
+    ///     .finish();
+    /// assert_eq!(jar.get("p_ab_id").unwrap().expires_datetime(), None);
+    /// ```
+    pub fn missing_expiry_is_session(mut self, session: bool) -> Self {
+        self.missing_expiry_is_session = session;
+        self
+    }
+    /// Returns the `(domain, name)` pairs that were parsed more than once, sorted
+    ///
+    /// Names are the ones in the source, before [`rename`](Self::rename) is applied.
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let content = ".pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7\n.pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	8\n";
+    /// let builder = CookieJarBuilder::new().parse(content).unwrap();
+    /// assert_eq!(builder.report_duplicates(), [(".pixiv.net".to_owned(), "p_ab_id".to_owned())]);
     /// ```
-    pub fn new() -> Self {
-        Self::default()
+    pub fn report_duplicates(&self) -> Vec<(String, String)> {
+        let mut duplicates: Vec<_> = self
+            .occurrences
+            .iter()
+            .filter(|(_, &n)| n > 1)
+            .map(|(key, _)| key.clone())
+            .collect();
+        duplicates.sort();
+        duplicates
     }
-    /// Creates a new `CookieJarBuilder` from a [`CookieJar`](cookie::CookieJar)
-    /// parsed cookies will be added to it
-    pub fn with_jar(jar: CookieJar) -> Self {
-        Self { jar }
+    /// Makes session cookies expire `max_age` after they are parsed
+    ///
+    /// Expirations past the year 9999 are clamped to its end.
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    /// use std::time::Duration;
+    ///
+    /// let content = ".pixiv.net	TRUE	/	TRUE	0	p_ab_id	7\n";
+    /// let jar = CookieJarBuilder::new()
+    ///     .default_max_age(Duration::from_secs(3600))
+    ///     .parse(content)
+    ///     .unwrap()
+    ///     .finish();
+    /// assert!(jar.get("p_ab_id").unwrap().expires_datetime().is_some());
+    /// ```
+    pub fn default_max_age(mut self, max_age: Duration) -> Self {
+        self.default_max_age = Some(max_age);
+        self
     }
-    /// Opens a file with `path` and parses it as cookies
+    /// Sets whether lines made only of whitespace are reported as warnings
+    ///
+    /// Such lines are still skipped like empty lines, but are recorded as `ParseError::WhitespaceLine`
+    /// in [`warnings`](Self::warnings), since they usually come from a mangling editor.
+    /// Lines containing the field separator, a tab unless changed with [`delimiter`](Self::delimiter),
+    /// are not reported. Defaults to `false`.
     ///
     /// ```
     /// use nescookie::CookieJarBuilder;
     ///
-    /// let jar = CookieJarBuilder::new().open("tests/cookies.txt").unwrap().finish();
+    /// let content = ".pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7\n   \n";
+    /// let builder = CookieJarBuilder::new().flag_whitespace_lines(true).parse(content).unwrap();
+    /// assert_eq!(builder.warnings().len(), 1);
     /// ```
-    pub fn open(self, path: impl AsRef<Path>) -> Result<Self, Error> {
-        self.parse_buffer(BufReader::new(File::open(path)?))
+    pub fn flag_whitespace_lines(mut self, flag: bool) -> Self {
+        self.flag_whitespace_lines = flag;
+        self
     }
-    /// Parses cookies from something that implements [`BufRead`](std::io::BufRead)
+    /// Keeps at most `n` cookies for each domain, like browsers do
+    ///
+    /// Once a domain has `n` cookies, later cookies for it are dropped,
+    /// unless they replace a cookie with the same name.
     ///
     /// ```
     /// use nescookie::CookieJarBuilder;
-    /// use std::io::Cursor;
     ///
-    /// let buf = Cursor::new(b".pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7\n");
-    /// let jar = CookieJarBuilder::new().parse_buffer(buf).unwrap().finish();
+    /// let jar = CookieJarBuilder::new()
+    ///     .max_cookies_per_domain(1)
+    ///     .open("tests/cookies.txt")
+    ///     .unwrap()
+    ///     .finish();
+    /// assert_eq!(jar.iter().count(), 2);
+    /// ```
+    pub fn max_cookies_per_domain(mut self, n: usize) -> Self {
+        self.max_cookies_per_domain = Some(n);
+        self
+    }
+    /// Sets which spellings are accepted for the secure field
+    ///
+    /// ```
+    /// use nescookie::{BoolStyle, CookieJarBuilder};
+    ///
+    /// let content = ".pixiv.net	1	/	yes	1784339332	p_ab_id	7\n";
+    /// let jar = CookieJarBuilder::new()
+    ///     .bool_tokens(BoolStyle::Lenient)
+    ///     .parse(content)
+    ///     .unwrap()
+    ///     .finish();
+    /// assert_eq!(jar.get("p_ab_id").unwrap().secure(), Some(true));
     /// ```
-    pub fn parse_buffer(self, mut buf: impl BufRead) -> Result<Self, Error> {
+    pub fn bool_tokens(mut self, style: BoolStyle) -> Self {
+        self.bool_style = style;
+        self
+    }
+    /// Sets whether the source lines are kept, so that [`write`](Self::write) can reproduce them
+    ///
+    /// Lines that failed to parse but were skipped because of [`on_error`](Self::on_error) or
+    /// [`tolerate`](Self::tolerate) are written back as they were. Lines of cookies changed by
+    /// options such as [`rename`](Self::rename) or [`force_path`](Self::force_path) no longer read
+    /// as those cookies, so they are written in the canonical format instead.
+    /// Line endings are not kept: every line is written back ending with `\n`,
+    /// so a source with `\r\n` line endings or without a final line break is not reproduced byte for byte.
+    /// Defaults to `false`.
+    pub fn keep_raw(mut self, keep: bool) -> Self {
+        self.keep_raw = keep;
+        self
+    }
+    /// Sets whether comment and blank lines are kept in order, so that [`write`](Self::write)
+    /// can re-emit them between the cookies they were read with
+    ///
+    /// Unlike [`keep_raw`](Self::keep_raw), cookies are written in the canonical format,
+    /// so changes to them are reflected in place. Defaults to `false`.
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let content = ".pixiv.net	TRUE	/	TRUE	1784339332	a	1\n# b comes next\n.pixiv.net	TRUE	/	TRUE	1784339332	b	2\n";
+    /// let builder = CookieJarBuilder::new().preserve_comments(true).parse(content).unwrap();
+    /// let mut buf = Vec::new();
+    /// builder.write(&mut buf).unwrap();
+    /// assert_eq!(buf, content.as_bytes());
+    /// ```
+    pub fn preserve_comments(mut self, preserve: bool) -> Self {
+        self.preserve_comments = preserve;
+        self
+    }
+    /// Writes the cookies in the builder to `w`
+    ///
+    /// With [`keep_raw`](Self::keep_raw), comments, skipped invalid lines and the lines of
+    /// cookies that are still unchanged in the jar are written as they were read, followed by
+    /// any other cookie in the jar. With [`preserve_comments`](Self::preserve_comments),
+    /// comments are written as they were read and cookies still in the jar are written
+    /// in their place, followed by any other cookie in the jar.
+    /// Otherwise this is the same as [`CookieJarWriter::write`].
+    /// Line endings are always normalized to `\n`.
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let content = std::fs::read_to_string("tests/cookies.txt").unwrap();
+    /// let builder = CookieJarBuilder::new().keep_raw(true).parse(&content).unwrap();
+    /// let mut buf = Vec::new();
+    /// builder.write(&mut buf).unwrap();
+    /// assert_eq!(buf, content.as_bytes());
+    /// ```
+    pub fn write(&self, mut w: impl io::Write) -> Result<(), Error> {
+        if !self.keep_raw && !self.preserve_comments {
+            return CookieJarWriter::new().write(&self.jar, w);
+        }
+        let mut written = HashSet::new();
         let mut s = String::new();
-        buf.read_to_string(&mut s)?;
-        self.parse(&s)
+        for (line, raw) in &self.raw_lines {
+            match raw {
+                RawLine::Cookie(cookie, verbatim) => {
+                    let current = match self.jar.get(cookie.name()) {
+                        Some(current) if !written.contains(cookie.name()) => current,
+                        _ => continue,
+                    };
+                    if self.keep_raw && current == cookie && *verbatim {
+                        s.push_str(line);
+                    } else if (self.keep_raw && current == cookie) || self.preserve_comments {
+                        s.push_str(&to_record(current).to_string());
+                    } else {
+                        continue;
+                    }
+                    written.insert(cookie.name());
+                }
+                RawLine::Failed if self.keep_raw => s.push_str(line),
+                RawLine::Failed => continue,
+                RawLine::Other => {
+                    let c = line.trim_start();
+                    if !c.trim().is_empty() && (!c.starts_with('#') || c.starts_with("#HttpOnly_"))
+                    {
+                        continue;
+                    }
+                    s.push_str(line);
+                }
+            }
+            s.push('\n');
+        }
+        let mut rest = CookieJar::new();
+        for c in self.jar.iter().filter(|c| !written.contains(c.name())) {
+            rest.add(c.clone());
+        }
+        for line in CookieJarWriter::new().records(&rest) {
+            s.push_str(&line);
+            s.push('\n');
+        }
+        w.write_all(s.as_bytes())?;
+        Ok(())
     }
-    /// Parses cookies from an str
+    /// Sets whether records commented out with a plain `#` are parsed as cookies
+    ///
+    /// Only comment lines with at least 7 tab-separated fields that parse as a valid
+    /// record are affected. Defaults to `false`.
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let content = "#.pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7\n";
+    /// let jar = CookieJarBuilder::new().parse_disabled(true).parse(content).unwrap().finish();
+    /// assert_eq!(jar.get("p_ab_id").unwrap().value(), "7");
+    /// ```
+    pub fn parse_disabled(mut self, parse: bool) -> Self {
+        self.parse_disabled = parse;
+        self
+    }
+    /// Sets whether the leading dot of domains follows the subdomain field instead of the domain as written
+    ///
+    /// With `TRUE` the domain is stored with a leading dot and with `FALSE` without one.
+    /// Defaults to `false`, so the subdomain field is ignored.
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let content = "pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7\n";
+    /// let jar = CookieJarBuilder::new().domain_from_flag(true).parse(content).unwrap().finish();
+    /// assert_eq!(jar.get("p_ab_id").unwrap().domain(), Some(".pixiv.net"));
+    /// ```
+    pub fn domain_from_flag(mut self, from_flag: bool) -> Self {
+        self.domain_from_flag = from_flag;
+        self
+    }
+    /// Sets what happens when a line fails to parse
+    ///
+    /// [`tolerate`](Self::tolerate) is only consulted with [`ErrorMode::FailFast`].
+    ///
+    /// ```
+    /// use nescookie::{CookieJarBuilder, ErrorMode};
+    ///
+    /// let content = ".pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7\nnot a cookie\n";
+    /// let builder = CookieJarBuilder::new().on_error(ErrorMode::Collect).parse(content).unwrap();
+    /// assert_eq!(builder.warnings().len(), 1);
+    /// ```
+    pub fn on_error(mut self, mode: ErrorMode) -> Self {
+        self.error_mode = mode;
+        self
+    }
+    /// Sets whether cookies with an empty name are accepted
+    ///
+    /// Such cookies are invalid per RFC 6265 but appear in broken files.
+    /// Defaults to `false`, rejecting them with `ParseError::InvalidName`.
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let content = ".pixiv.net	TRUE	/	TRUE	1784339332		7\n";
+    /// assert!(CookieJarBuilder::new().parse(content).is_err());
+    /// let jar = CookieJarBuilder::new().allow_empty_name(true).parse(content).unwrap().finish();
+    /// assert_eq!(jar.get("").unwrap().value(), "7");
+    /// ```
+    pub fn allow_empty_name(mut self, allow: bool) -> Self {
+        self.allow_empty_name = allow;
+        self
+    }
+    /// Sets whether values are percent-decoded, so that `10%3A48` is stored as `10:48`
+    ///
+    /// Invalid UTF-8 after decoding is replaced with `U+FFFD`.
+    /// Defaults to `false`, keeping values as written for faithful round trips.
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let content = "www.pixiv.net	FALSE	/	TRUE	0	first_visit_datetime_pc	2021-07-19+10%3A48%3A50\n";
+    /// let jar = CookieJarBuilder::new().decode_values(true).parse(content).unwrap().finish();
+    /// assert_eq!(jar.get("first_visit_datetime_pc").unwrap().value(), "2021-07-19+10:48:50");
+    /// ```
+    #[cfg(feature = "percent-encoding")]
+    pub fn decode_values(mut self, decode: bool) -> Self {
+        self.decode_values = decode;
+        self
+    }
+    /// Sets the character separating the fields of a line, which defaults to a tab
+    ///
+    /// Any character other than `\n` can be used, including control characters such as `\0`.
+    /// Parsing with `\n` fails with `Error::InvalidDelimiter`, since it would split every record.
     ///
     /// ```
     /// use nescookie::CookieJarBuilder;
     ///
+    /// let content = ".pixiv.net|TRUE|/|TRUE|1784339332|p_ab_id|7\n";
+    /// let jar = CookieJarBuilder::new().delimiter('|').parse(content).unwrap().finish();
+    /// assert_eq!(jar.get("p_ab_id").unwrap().value(), "7");
+    /// ```
+    pub fn delimiter(mut self, delimiter: char) -> Self {
+        self.delimiter = Some(delimiter);
+        self
+    }
+    /// Sets the `SameSite` attribute of every parsed cookie to `same_site`
+    ///
+    /// The netscape format has no field for it, so cookies have none by default.
+    ///
+    /// ```
+    /// use nescookie::{CookieJarBuilder, SameSite};
+    ///
     /// let content = ".pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7\n";
-    /// let jar = CookieJarBuilder::new().parse(content).unwrap().finish();
+    /// let jar = CookieJarBuilder::new()
+    ///     .default_same_site(SameSite::Lax)
+    ///     .parse(content)
+    ///     .unwrap()
+    ///     .finish();
+    /// assert_eq!(jar.get("p_ab_id").unwrap().same_site(), Some(SameSite::Lax));
     /// ```
-    pub fn parse(mut self, s: &str) -> Result<Self, Error> {
-        // todo: check if there is a newline before eof
-        for c in s.lines().map(|s| s.trim()).filter(|s| !s.is_empty()) {
-            let (http_only, mut fileds) = if c.starts_with('#') {
-                if c.starts_with("#HttpOnly_") {
-                    (true, c.trim_start_matches("#HttpOnly_").split('\t'))
-                } else {
+    pub fn default_same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = Some(same_site);
+        self
+    }
+    /// Keeps only cookies whose path starts with `prefix`
+    ///
+    /// The path is checked as written in the source, before [`force_path`](Self::force_path) is applied.
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let content = ".pixiv.net	TRUE	/	TRUE	1784339332	a	1\n.pixiv.net	TRUE	/api	TRUE	1784339332	b	2\n";
+    /// let jar = CookieJarBuilder::new().path_prefix("/api").parse(content).unwrap().finish();
+    /// assert!(jar.get("a").is_none());
+    /// assert!(jar.get("b").is_some());
+    /// ```
+    pub fn path_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.path_prefix = Some(prefix.into());
+        self
+    }
+    /// Sets whether lines that look like they lack the path field are parsed with a path of `/`
+    ///
+    /// A line is repaired if it has 6 fields, its third field is a boolean where the path would be,
+    /// and its fourth field is a unix timestamp where the secure flag would be.
+    /// Lines missing their value or expiration have a path in the third field, so they are not affected.
+    /// This takes precedence over [`missing_expiry_is_session`](Self::missing_expiry_is_session).
+    /// Defaults to `false`.
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let content = ".pixiv.net	TRUE	TRUE	1784339332	p_ab_id	7\n";
+    /// let jar = CookieJarBuilder::new().repair_missing_path(true).parse(content).unwrap().finish();
+    /// assert_eq!(jar.get("p_ab_id").unwrap().path(), Some("/"));
+    /// ```
+    pub fn repair_missing_path(mut self, repair: bool) -> Self {
+        self.repair_missing_path = repair;
+        self
+    }
+    /// Parses cookies from something that implements [`BufRead`](std::io::BufRead) line by line,
+    /// handing each one to `sink` instead of adding them to the jar
+    ///
+    /// The options of the builder apply as with [`parse`](Self::parse), and warnings and metadata
+    /// are kept in the builder. With [`prefer_latest_expiry`](Self::prefer_latest_expiry) or
+    /// [`max_cookies_per_domain`](Self::max_cookies_per_domain), the cookies handed to `sink`
+    /// are remembered to compare later ones against, but are never taken back.
+    /// Parsing stops early once `sink` returns `ControlFlow::Break`.
+    /// Lines are checked against [`max_line_len`](Self::max_line_len) as they are read,
+    /// so a line that is too long is never kept in memory as a whole.
+    ///
+    /// ```
+    /// use nescookie::{error::{Error, ParseError}, CookieJarBuilder};
+    /// use std::{io::Cursor, ops::ControlFlow};
+    ///
+    /// let buf = Cursor::new(b".pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7\n");
+    /// let result = CookieJarBuilder::new()
+    ///     .max_line_len(16)
+    ///     .parse_streaming(buf, |_| ControlFlow::Continue(()));
+    /// assert!(matches!(
+    ///     result,
+    ///     Err(Error::ParseError(ParseError::LineTooLong { line: 1, len: 43 }))
+    /// ));
+    /// ```
+    pub fn parse_streaming(
+        &mut self,
+        mut buf: impl BufRead,
+        mut sink: impl FnMut(Cookie<'static>) -> ControlFlow<()>,
+    ) -> Result<(), Error> {
+        if self.separator() == '\n' {
+            return Err(Error::InvalidDelimiter('\n'));
+        }
+        let max = self.max_line_len.unwrap_or(usize::MAX);
+        let remember = self.prefer_latest_expiry || self.max_cookies_per_domain.is_some();
+        let mut streamed = CookieJar::new();
+        let mut pos = Position::default();
+        while let Some(line) = read_line(&mut buf, max)? {
+            let i = pos.lines;
+            pos.lines += 1;
+            let text = strip_bom(&line.text, i);
+            let len = text.len() + line.len - line.text.len();
+            let cookie = match self.parse_source_line(text, len, i, None, &mut pos, &streamed)? {
+                LineOutcome::Cookie(cookie) => cookie,
+                LineOutcome::Stop => break,
+                LineOutcome::Skipped | LineOutcome::Failed => continue,
+            };
+            pos.added += 1;
+            if remember {
+                streamed.add(cookie.clone());
+            }
+            if sink(cookie).is_break() {
+                break;
+            }
+        }
+        Ok(())
+    }
+    /// Returns the character separating fields, see [`delimiter`](Self::delimiter)
+    fn separator(&self) -> char {
+        self.delimiter.unwrap_or('\t')
+    }
+    fn parse_source(mut self, s: &str, source: Option<&Path>) -> Result<Self, Error> {
+        self.parse_into(
+            s.split_inclusive('\n'),
+            source,
+            None,
+            &mut Position::default(),
+        )?;
+        Ok(self)
+    }
+    fn parse_into(
+        &mut self,
+        lines: impl IntoIterator<Item = impl SourceLine>,
+        source: Option<&Path>,
+        errors: Option<&mut Vec<ParseError>>,
+        pos: &mut Position,
+    ) -> Result<(), Error> {
+        // the jar is moved out, so that new cookies can be compared against it
+        let mut jar = std::mem::take(&mut self.jar);
+        let result = self.parse_into_jar(&mut jar, lines, source, errors, pos);
+        self.jar = jar;
+        result
+    }
+    fn parse_into_jar(
+        &mut self,
+        jar: &mut CookieJar,
+        lines: impl IntoIterator<Item = impl SourceLine>,
+        source: Option<&Path>,
+        mut errors: Option<&mut Vec<ParseError>>,
+        pos: &mut Position,
+    ) -> Result<(), Error> {
+        if self.separator() == '\n' {
+            return Err(Error::InvalidDelimiter('\n'));
+        }
+        let start = Instant::now();
+        let added_before = pos.added;
+        let (mut lines_seen, mut bytes_read) = (0, 0);
+        pos.domain_counts.clear();
+        if self.max_cookies_per_domain.is_some() {
+            for c in jar.iter() {
+                *pos.domain_counts
+                    .entry(c.domain().map(str::to_owned))
+                    .or_default() += 1;
+            }
+        }
+        // a builder with only the options on how fields are read
+        let plain = self.keep_raw.then(|| Self {
+            delimiter: self.delimiter,
+            csv_mode: self.csv_mode,
+            collapse_tabs: self.collapse_tabs,
+            tolerant_columns: self.tolerant_columns,
+            repair_missing_path: self.repair_missing_path,
+            missing_expiry_is_session: self.missing_expiry_is_session,
+            bool_style: self.bool_style,
+            allow_missing_value: self.allow_missing_value,
+            allow_empty_name: self.allow_empty_name,
+            parse_disabled: self.parse_disabled,
+            keep_trailing_dot: self.keep_trailing_dot,
+            ..Self::default()
+        });
+        for line in lines {
+            let i = pos.lines;
+            pos.lines += 1;
+            lines_seen += 1;
+            bytes_read += line.bytes();
+            if self.keep_raw || self.preserve_comments {
+                self.raw_lines
+                    .push((line.text().to_owned(), RawLine::Other));
+            }
+            let c = strip_bom(line.text(), i);
+            // the length of a truncated line is larger than that of its text
+            let len = c.len() + line.source_len() - line.text().len();
+            let cookie = match self.parse_source_line(c, len, i, errors.as_deref_mut(), pos, jar)? {
+                LineOutcome::Cookie(cookie) => cookie,
+                LineOutcome::Stop => break,
+                LineOutcome::Skipped => continue,
+                LineOutcome::Failed => {
+                    if let Some((_, raw)) = self.raw_lines.last_mut() {
+                        *raw = RawLine::Failed;
+                    }
                     continue;
                 }
-            } else {
-                (false, c.split('\t'))
             };
-            let domain = fileds.next().ok_or(ParseError::TooFewFileds)?;
-            let _ = fileds.next(); // ignore subdomain
-            let path = fileds.next().ok_or(ParseError::TooFewFileds)?;
-            let secure = match fileds.next().ok_or(ParseError::TooFewFileds)? {
-                "TRUE" => true,
-                "FALSE" => false,
-                value => return Err(ParseError::InvaildValue(value.to_owned()).into()),
+            match source {
+                Some(path) => self
+                    .sources
+                    .insert(cookie.name().to_owned(), path.to_owned()),
+                None => self.sources.remove(cookie.name()),
             };
-            let expiration: i64 = match fileds.next() {
-                Some(value) => match value.parse() {
-                    Ok(v) => v,
-                    Err(_) => return Err(ParseError::InvaildValue(value.to_owned()).into()),
-                },
-                _ => return Err(ParseError::TooFewFileds.into()),
+            if self.keep_raw || self.preserve_comments {
+                // the line is only written as is if it reads as the cookie without other options
+                let verbatim = plain.as_ref().is_some_and(
+                    |plain| matches!(plain.parse_line(c, i + 1), Ok(Some(read)) if read == cookie),
+                );
+                // the current line was pushed at the start of the iteration
+                if let Some((_, raw)) = self.raw_lines.last_mut() {
+                    *raw = RawLine::Cookie(cookie.clone(), verbatim);
+                }
+            }
+            jar.add(cookie);
+            pos.added += 1;
+        }
+        if let Some(metrics) = &self.metrics {
+            if let Some(f) = metrics.lock().take() {
+                f(ParseMetrics {
+                    lines_seen,
+                    cookies_added: pos.added - added_before,
+                    bytes_read,
+                    elapsed: start.elapsed(),
+                });
+            }
+        }
+        Ok(())
+    }
+    /// Parses the 0-based `i`th line `c` of a source and applies the options of the builder to its cookie
+    ///
+    /// `len` is the length of the line in the source, and `jar` holds the cookies added so far.
+    fn parse_source_line(
+        &mut self,
+        c: &str,
+        len: usize,
+        i: usize,
+        mut errors: Option<&mut Vec<ParseError>>,
+        pos: &mut Position,
+        jar: &CookieJar,
+    ) -> Result<LineOutcome, Error> {
+        if c.trim().is_empty() && len == c.len() {
+            if self.flag_whitespace_lines && !c.is_empty() && !c.contains(self.separator()) {
+                self.warnings.push((i + 1, ParseError::WhitespaceLine));
+            }
+            return Ok(LineOutcome::Skipped);
+        }
+        if self.sniff && !pos.sniffed {
+            let c = c.trim_start();
+            if !c.starts_with('#') || c.starts_with("#HttpOnly_") {
+                if c.split(self.separator()).count() < 6 {
+                    return Err(Error::NotNetscapeFormat);
+                }
+                pos.sniffed = true;
+            }
+        }
+        if self.head.is_some_and(|n| pos.added >= n) {
+            return Ok(LineOutcome::Stop);
+        }
+        if c.trim() == writer::HEADER {
+            // the start of another concatenated file
+            pos.seen.clear();
+        }
+        if self.extended_comments {
+            let directive = c.trim().strip_prefix("#@").and_then(|d| d.split_once('='));
+            if let Some((key, value)) = directive {
+                self.metadata
+                    .insert(key.trim().to_owned(), value.trim().to_owned());
+                return Ok(LineOutcome::Skipped);
+            }
+        }
+        let cookie = match self.max_line_len {
+            Some(max) if len > max => Err(ParseError::LineTooLong { line: i + 1, len }),
+            _ => self.parse_line(c, i + 1),
+        };
+        let mut cookie = match cookie {
+            Ok(Some(cookie)) => cookie,
+            Ok(None) => return Ok(LineOutcome::Skipped),
+            Err(e) => {
+                self.recover(errors.as_deref_mut(), i + 1, e)?;
+                return Ok(LineOutcome::Failed);
+            }
+        };
+        *self
+            .occurrences
+            .entry((
+                cookie.domain().unwrap_or_default().to_owned(),
+                cookie.name().to_owned(),
+            ))
+            .or_default() += 1;
+        if let Some(rename) = &self.rename {
+            let name = (rename.lock())(cookie.name());
+            cookie.set_name(name);
+        }
+        if let (Some(max_age), None) = (self.default_max_age, cookie.expires_datetime()) {
+            let exp = checked_add(OffsetDateTime::now_utc(), max_age)
+                .unwrap_or_else(|| OffsetDateTime::from_unix_timestamp(MAX_TIMESTAMP));
+            cookie.set_expires(exp);
+        }
+        if matches!((self.now, cookie.expires_datetime()), (Some(now), Some(exp)) if exp <= now) {
+            return Ok(LineOutcome::Skipped);
+        }
+        if !pos.seen.insert(cookie.name().to_owned()) {
+            match self.duplicate_policy {
+                DuplicatePolicy::Overwrite => {}
+                DuplicatePolicy::KeepFirst => return Ok(LineOutcome::Skipped),
+                DuplicatePolicy::Error => {
+                    let e = ParseError::DuplicateName(cookie.name().to_owned());
+                    self.recover(errors, i + 1, e)?;
+                    return Ok(LineOutcome::Failed);
+                }
+            }
+        }
+        if self.prefer_latest_expiry {
+            // session cookies are treated as the latest
+            let key = |c: &Cookie| {
+                let exp = c.expires_datetime();
+                (exp.is_none(), exp)
             };
-            let name = fileds.next().ok_or(ParseError::TooFewFileds)?;
-            let value = fileds.next().ok_or(ParseError::TooFewFileds)?;
-            let cookie = Cookie::build(name, value)
+            if matches!(jar.get(cookie.name()), Some(old) if key(old) > key(&cookie)) {
+                return Ok(LineOutcome::Skipped);
+            }
+        }
+        if let Some(max) = self.max_cookies_per_domain {
+            let domain = cookie.domain().map(str::to_owned);
+            let old_domain = jar.get(cookie.name()).map(|old| old.domain());
+            // a cookie replacing one of the same domain does not take a new slot
+            let replaced = usize::from(old_domain == Some(cookie.domain()));
+            if pos.domain_counts.get(&domain).map_or(0, |n| n - replaced) >= max {
+                return Ok(LineOutcome::Skipped);
+            }
+            if let Some(old_domain) = old_domain {
+                if let Some(n) = pos.domain_counts.get_mut(&old_domain.map(str::to_owned)) {
+                    *n -= 1;
+                }
+            }
+            *pos.domain_counts.entry(domain).or_default() += 1;
+        }
+        Ok(LineOutcome::Cookie(cookie))
+    }
+    /// Handles the error `e` of the 1-based `line`, either skipping it, collecting it or failing with it
+    fn recover(
+        &mut self,
+        errors: Option<&mut Vec<ParseError>>,
+        line: usize,
+        e: ParseError,
+    ) -> Result<(), Error> {
+        match errors {
+            _ if self.error_mode == ErrorMode::Skip => {}
+            _ if self.error_mode == ErrorMode::Collect => self.warnings.push((line, e)),
+            _ if matches!(&self.tolerate, Some(tolerate) if (tolerate.lock())(&e)) => {
+                self.warnings.push((line, e))
+            }
+            Some(errors) => errors.push(e),
+            None => return Err(e.into()),
+        }
+        Ok(())
+    }
+    /// Parses the `n`th line of a source, which is 1-based
+    fn parse_line(&self, line: &str, n: usize) -> Result<Option<Cookie<'static>>, ParseError> {
+        let delimiter = self.separator();
+        // keep trailing delimiters, which separate an empty value
+        let c = line
+            .trim_start()
+            .trim_end_matches(|c: char| c != delimiter && c.is_whitespace());
+        let (http_only, c) = if let Some(comment) = c.strip_prefix('#') {
+            match comment.strip_prefix("HttpOnly_") {
+                // allow whitespace between the prefix and the domain
+                Some(c) => (true, c.trim_start()),
+                None if self.parse_disabled && comment.split(delimiter).count() >= 7 => {
+                    // a record that was commented out, which is kept as a comment if it is invalid
+                    let record = comment.trim_start();
+                    let (http_only, record) = match record.strip_prefix("#HttpOnly_") {
+                        Some(record) => (true, record.trim_start()),
+                        // only one level of comments is removed
+                        None if record.starts_with('#') => return Ok(None),
+                        None => (false, record),
+                    };
+                    return Ok(self.parse_record(record, http_only, n).ok().flatten());
+                }
+                None if self.disallow_comments && c != writer::HEADER => {
+                    return Err(ParseError::UnexpectedComment { line: n })
+                }
+                None => return Ok(None),
+            }
+        } else {
+            (false, c)
+        };
+        self.parse_record(c, http_only, n)
+    }
+    /// Parses the fields of the `n`th line of a source, after its `#HttpOnly_` prefix is removed
+    fn parse_record(
+        &self,
+        c: &str,
+        http_only: bool,
+        n: usize,
+    ) -> Result<Option<Cookie<'static>>, ParseError> {
+        let delimiter = self.separator();
+        let mut fileds: Vec<_> = if self.csv_mode {
+            split_quoted(c, delimiter)
+        } else {
+            c.split(delimiter).map(Cow::Borrowed).collect()
+        };
+        if self.collapse_tabs && delimiter == '\t' {
+            fileds.retain(|f| !f.is_empty());
+        }
+        // domain, subdomains, secure, expiration, name and value, without the path
+        if self.repair_missing_path
+            && fileds.len() == 6
+            && self.bool_style.parse(&fileds[2]).is_some()
+            && fileds[3].parse::<i64>().is_ok()
+        {
+            fileds.insert(2, Cow::Borrowed("/"));
+        }
+        if self.missing_expiry_is_session && fileds.len() == 6 {
+            fileds.insert(4, Cow::Borrowed("0"));
+        }
+        let mut fileds = fileds.iter().map(|f| f.as_ref());
+        let domain = fileds.next().ok_or(ParseError::TooFewFileds)?;
+        let domain = match domain.strip_suffix('.') {
+            Some(stripped) if !self.keep_trailing_dot => stripped,
+            _ => domain,
+        };
+        let subdomains = fileds.next();
+        let domain = match subdomains {
+            Some(flag) if self.domain_from_flag => {
+                let bare = domain.trim_start_matches('.');
+                match self.bool_style.parse(flag) {
+                    Some(true) => Cow::Owned(format!(".{}", bare)),
+                    Some(false) => Cow::Borrowed(bare),
+                    None => return Err(ParseError::InvaildValue(flag.to_owned())),
+                }
+            }
+            _ => Cow::Borrowed(domain),
+        };
+        let path = fileds.next().ok_or(ParseError::TooFewFileds)?;
+        let filtered =
+            matches!(&self.path_prefix, Some(prefix) if !path.starts_with(prefix.as_str()));
+        let path = self.force_path.as_deref().unwrap_or(path);
+        let secure = fileds.next().ok_or(ParseError::TooFewFileds)?;
+        let expiration = fileds.next().ok_or(ParseError::TooFewFileds)?;
+        let (secure, expiration) = if self.tolerant_columns
+            && secure.parse::<i64>().is_ok()
+            && self.bool_style.parse(expiration).is_some()
+        {
+            (expiration, secure)
+        } else {
+            (secure, expiration)
+        };
+        let secure = match self.bool_style.parse(secure) {
+            Some(secure) => secure,
+            None => return Err(ParseError::InvaildValue(secure.to_owned())),
+        };
+        let secure = self.force_secure.unwrap_or(secure);
+        let expiration = match expiration.parse() {
+            Ok(0) => None,
+            // some JavaScript based exporters write session cookies this way
+            Err(_) if matches!(expiration, "" | "Infinity") => None,
+            Ok(exp) => match timestamp(exp) {
+                Some(t) => Some(t),
+                None => return Err(ParseError::InvaildValue(expiration.to_owned())),
+            },
+            // some tools write the expiration as an RFC 3339 datetime
+            Err(_) => match OffsetDateTime::parse(expiration, Format::Rfc3339) {
+                Ok(t) => Some(t),
+                Err(_) => return Err(ParseError::InvaildValue(expiration.to_owned())),
+            },
+        };
+        let name = fileds.next().ok_or(ParseError::TooFewFileds)?;
+        if (self.validate_names && !is_token(name)) || (name.is_empty() && !self.allow_empty_name) {
+            return Err(ParseError::InvalidName {
+                line: n,
+                name: name.to_owned(),
+            });
+        }
+        let value = match fileds.next() {
+            Some(value) => value,
+            None if self.allow_missing_value => "",
+            None => return Err(ParseError::TooFewFileds),
+        };
+        #[cfg(feature = "percent-encoding")]
+        let value = match self.decode_values {
+            true => percent_encoding::percent_decode_str(value).decode_utf8_lossy(),
+            false => Cow::Borrowed(value),
+        };
+        if filtered {
+            return Ok(None);
+        }
+        let cookie = if self.bare {
+            Cookie::build(name, value)
+        } else {
+            Cookie::build(name, value)
                 .domain(domain)
                 .path(path)
                 .secure(secure)
-                .expires(match expiration {
-                    0 => None,
-                    exp => Some(OffsetDateTime::from_unix_timestamp(exp)),
-                });
-            let cookie = if http_only {
-                cookie.http_only(true).finish()
-            } else {
-                cookie.finish()
-            };
-            self.jar.add(cookie.into_owned());
         }
-        Ok(self)
+        .expires(expiration);
+        let cookie = match self.same_site {
+            Some(same_site) => cookie.same_site(same_site),
+            None => cookie,
+        };
+        let cookie = if self.force_http_only.unwrap_or(http_only) {
+            cookie.http_only(true).finish()
+        } else {
+            cookie.finish()
+        };
+        Ok(Some(cookie.into_owned()))
     }
     /// Returns the built `CookieJar`
     pub fn finish(self) -> CookieJar {
@@ -118,6 +1859,24 @@ impl CookieJarBuilder {
     }
 }
 
+/// Adds cookies that were built by hand, replacing parsed ones with the same name
+///
+/// ```
+/// use nescookie::{Cookie, CookieJarBuilder};
+///
+/// let mut builder = CookieJarBuilder::new();
+/// builder.extend(vec![Cookie::new("a", "1"), Cookie::new("b", "2")]);
+/// assert_eq!(builder.finish().iter().count(), 2);
+/// ```
+impl Extend<Cookie<'static>> for CookieJarBuilder {
+    fn extend<T: IntoIterator<Item = Cookie<'static>>>(&mut self, iter: T) {
+        for cookie in iter {
+            self.sources.remove(cookie.name());
+            self.jar.add(cookie);
+        }
+    }
+}
+
 /// Opens a file with `path` and parses it as [`CookieJar`](cookie::CookieJar)
 ///
 /// ```
@@ -127,6 +1886,21 @@ impl CookieJarBuilder {
 pub fn open(path: impl AsRef<Path>) -> Result<CookieJar, Error> {
     CookieJarBuilder::new().open(path).map(|jar| jar.finish())
 }
+/// Opens a file with `path` and returns the builder, so that more cookies can be added before finishing
+///
+/// ```
+/// let jar = nescookie::open_builder("tests/cookies.txt")
+///     .unwrap()
+///     .parse(".pixiv.net	TRUE	/	TRUE	1784339332	extra	1\n")
+///     .unwrap()
+///     .finish();
+/// assert!(jar.get("PHPSESSID").is_some());
+/// assert!(jar.get("extra").is_some());
+/// ```
+#[inline]
+pub fn open_builder(path: impl AsRef<Path>) -> Result<CookieJarBuilder, Error> {
+    CookieJarBuilder::new().open(path)
+}
 /// Parses a [`CookieJar`](cookie::CookieJar) from something that implements [`BufRead`](std::io::BufRead)
 ///
 /// ```
@@ -141,6 +1915,18 @@ pub fn parse_buffer(buf: impl BufRead) -> Result<CookieJar, Error> {
         .parse_buffer(buf)
         .map(|jar| jar.finish())
 }
+/// Parses a [`CookieJar`](cookie::CookieJar) from UTF-8 encoded bytes
+///
+/// ```
+/// let content = b".pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7\n";
+/// let jar = nescookie::parse_bytes(content).unwrap();
+/// ```
+#[inline]
+pub fn parse_bytes(bytes: &[u8]) -> Result<CookieJar, Error> {
+    CookieJarBuilder::new()
+        .parse_bytes(bytes)
+        .map(|jar| jar.finish())
+}
 /// Parses a [`CookieJar`](cookie::CookieJar) from an str
 ///
 /// ```
@@ -151,3 +1937,215 @@ pub fn parse_buffer(buf: impl BufRead) -> Result<CookieJar, Error> {
 pub fn parse(s: &str) -> Result<CookieJar, Error> {
     CookieJarBuilder::new().parse(s).map(|jar| jar.finish())
 }
+/// Parses cookies from an str into a borrowed [`CookieJar`](cookie::CookieJar), returning how many were added
+///
+/// Cookies replace the ones with the same name already in `jar`.
+/// If parsing fails, `jar` is left unchanged.
+///
+/// ```
+/// let mut jar = nescookie::open("tests/cookies.txt").unwrap();
+/// let added = nescookie::parse_into(&mut jar, ".pixiv.net	TRUE	/	TRUE	1784339332	extra	1\n").unwrap();
+/// assert_eq!(added, 1);
+/// assert_eq!(jar.iter().count(), 7);
+/// ```
+pub fn parse_into(jar: &mut CookieJar, s: &str) -> Result<usize, Error> {
+    let parsed = parse(s)?;
+    let mut added = 0;
+    for c in parsed.iter() {
+        jar.add(c.clone());
+        added += 1;
+    }
+    Ok(added)
+}
+/// Parses cookies from a string, failing if it does not contain exactly `expected` cookies
+///
+/// This is mainly useful for catching changes in test fixtures.
+///
+/// ```
+/// use nescookie::error::Error;
+///
+/// let content = ".pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7\n";
+/// assert!(nescookie::parse_expecting(content, 1).is_ok());
+/// assert!(matches!(
+///     nescookie::parse_expecting(content, 2),
+///     Err(Error::CountMismatch { expected: 2, found: 1 })
+/// ));
+/// ```
+pub fn parse_expecting(s: &str, expected: usize) -> Result<CookieJar, Error> {
+    let jar = parse(s)?;
+    let found = jar.iter().count();
+    if found != expected {
+        return Err(Error::CountMismatch { expected, found });
+    }
+    Ok(jar)
+}
+/// Parses every line of `s` into a cookie, without stopping at the first error
+///
+/// Errors are paired with the 1-based line number they occurred on.
+/// Blank and comment lines are skipped.
+///
+/// ```
+/// let content = ".pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7\n.pixiv.net	TRUE\n";
+/// let results = nescookie::parse_results(content);
+/// assert!(results[0].is_ok());
+/// assert_eq!(results[1].as_ref().unwrap_err().0, 2);
+/// ```
+pub fn parse_results(s: &str) -> Vec<Result<Cookie<'static>, (usize, ParseError)>> {
+    line_results(s).collect()
+}
+/// Returns the first line of `s` that fails to parse with its 1-based line number, or `None` if all of them parse
+///
+/// Unlike [`validate`], this stops at the first error.
+///
+/// ```
+/// use nescookie::error::ParseError;
+///
+/// let content = ".pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7\n.pixiv.net	TRUE\n";
+/// assert_eq!(nescookie::first_error(content), Some((2, ParseError::TooFewFileds)));
+/// ```
+pub fn first_error(s: &str) -> Option<(usize, ParseError)> {
+    line_results(s).find_map(Result::err)
+}
+/// Lazily parses every line of `s` for [`parse_results`] and [`first_error`]
+fn line_results(
+    s: &str,
+) -> impl Iterator<Item = Result<Cookie<'static>, (usize, ParseError)>> + '_ {
+    let builder = CookieJarBuilder::new();
+    s.lines()
+        .enumerate()
+        .map(|(i, line)| (i, strip_bom(line, i)))
+        .filter(|(_, line)| !line.trim().is_empty())
+        .filter_map(move |(i, line)| {
+            builder
+                .parse_line(line, i + 1)
+                .map_err(|e| (i + 1, e))
+                .transpose()
+        })
+}
+/// Parses cookies from a string into a map keyed by their names
+///
+/// Later cookies replace earlier ones with the same name.
+///
+/// ```
+/// let content = ".pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7\n";
+/// let map = nescookie::parse_map(content).unwrap();
+/// assert_eq!(map["p_ab_id"].value(), "7");
+/// ```
+pub fn parse_map(s: &str) -> Result<HashMap<String, Cookie<'static>>, Error> {
+    Ok(parse(s)?
+        .iter()
+        .map(|c| (c.name().to_owned(), c.clone()))
+        .collect())
+}
+/// Parses cookies from something that implements [`BufRead`](std::io::BufRead) line by line,
+/// handing each one to `sink` instead of collecting them into a jar
+///
+/// Parsing stops early once `sink` returns `ControlFlow::Break`.
+/// See [`CookieJarBuilder::parse_streaming`] to limit the length of lines.
+///
+/// ```
+/// use std::{io::Cursor, ops::ControlFlow};
+///
+/// let buf = Cursor::new(b".pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7\n");
+/// let mut names = Vec::new();
+/// nescookie::parse_streaming(buf, |c| {
+///     names.push(c.name().to_owned());
+///     ControlFlow::Continue(())
+/// })
+/// .unwrap();
+/// assert_eq!(names, ["p_ab_id"]);
+/// ```
+#[inline]
+pub fn parse_streaming(
+    buf: impl BufRead,
+    sink: impl FnMut(Cookie<'static>) -> ControlFlow<()>,
+) -> Result<(), Error> {
+    CookieJarBuilder::new().parse_streaming(buf, sink)
+}
+/// Counts the cookie lines in `s` without constructing any cookies
+///
+/// Only the number of fields of each line is checked, so this is faster than
+/// [`parse`] but does not catch invalid values. Cookies sharing a name are all counted.
+///
+/// ```
+/// let content = "# Netscape HTTP Cookie File\n.pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7\n";
+/// assert_eq!(nescookie::count(content).unwrap(), 1);
+/// ```
+pub fn count(s: &str) -> Result<usize, Error> {
+    let mut count = 0;
+    let lines = s.lines().enumerate().map(|(i, line)| strip_bom(line, i));
+    for line in lines.map(str::trim_start).filter(|l| !l.is_empty()) {
+        let line = match line.strip_prefix("#HttpOnly_") {
+            Some(line) => line,
+            None if line.starts_with('#') => continue,
+            None => line,
+        };
+        if line.split('\t').count() < 7 {
+            return Err(ParseError::TooFewFileds.into());
+        }
+        count += 1;
+    }
+    Ok(count)
+}
+/// Parses the first `n` cookies of `s` into a [`CookieJar`](cookie::CookieJar), ignoring the rest
+///
+/// ```
+/// let jar = nescookie::parse_head(".pixiv.net	TRUE	/	TRUE	0	a	1\nnot a cookie\n", 1).unwrap();
+/// assert_eq!(jar.iter().count(), 1);
+/// ```
+pub fn parse_head(s: &str, n: usize) -> Result<CookieJar, Error> {
+    let builder = CookieJarBuilder {
+        head: Some(n),
+        ..Default::default()
+    };
+    builder.parse(s).map(|jar| jar.finish())
+}
+/// Writes a [`CookieJar`](cookie::CookieJar) to something that implements [`Write`](std::io::Write)
+///
+/// ```
+/// let jar = nescookie::open("tests/cookies.txt").unwrap();
+/// let mut buf = Vec::new();
+/// nescookie::write(&jar, &mut buf).unwrap();
+/// ```
+#[inline]
+pub fn write(jar: &CookieJar, w: impl io::Write) -> Result<(), Error> {
+    CookieJarWriter::new().write(jar, w)
+}
+/// Writes a [`CookieJar`](cookie::CookieJar) to a `String`
+///
+/// ```
+/// let jar = nescookie::open("tests/cookies.txt").unwrap();
+/// let s = nescookie::to_string(&jar);
+/// assert_eq!(nescookie::parse(&s).unwrap().iter().count(), 6);
+/// ```
+#[inline]
+pub fn to_string(jar: &CookieJar) -> String {
+    CookieJarWriter::new().write_string(jar)
+}
+/// Appends a [`CookieJar`](cookie::CookieJar) to the file at `path`, writing the header only if it is empty
+///
+/// ```no_run
+/// let jar = nescookie::parse(".pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7\n").unwrap();
+/// nescookie::append(&jar, "cookies.txt").unwrap();
+/// ```
+#[inline]
+pub fn append(jar: &CookieJar, path: impl AsRef<Path>) -> Result<(), Error> {
+    CookieJarWriter::new().append(jar, path)
+}
+/// Checks that every line of `s` parses, returning all the errors with their 1-based line numbers
+///
+/// ```
+/// let content = std::fs::read_to_string("tests/cookies.txt").unwrap();
+/// assert!(nescookie::validate(&content).is_ok());
+/// ```
+pub fn validate(s: &str) -> Result<(), Vec<(usize, ParseError)>> {
+    let errors: Vec<_> = parse_results(s)
+        .into_iter()
+        .filter_map(Result::err)
+        .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
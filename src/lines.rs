@@ -0,0 +1,47 @@
+use crate::{error::ParseError, strip_bom, CookieJarBuilder};
+use cookie::Cookie;
+use std::{iter::Enumerate, str::Lines};
+
+/// An iterator over the cookies of a netscape cookie file, parsing one line at a time
+///
+/// Comments and blank lines are skipped.
+///
+/// ```
+/// use nescookie::CookieLines;
+///
+/// let mut lines = CookieLines::new(".pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7\n");
+/// assert_eq!(lines.next().unwrap().unwrap().name(), "p_ab_id");
+/// assert!(lines.next().is_none());
+/// ```
+#[derive(Debug, Clone)]
+pub struct CookieLines<'a> {
+    lines: Enumerate<Lines<'a>>,
+    builder: CookieJarBuilder,
+}
+
+impl<'a> CookieLines<'a> {
+    /// Creates a new `CookieLines` over `s`
+    pub fn new(s: &'a str) -> Self {
+        Self {
+            lines: s.lines().enumerate(),
+            builder: CookieJarBuilder::new(),
+        }
+    }
+}
+
+impl Iterator for CookieLines<'_> {
+    type Item = Result<Cookie<'static>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (i, line) in &mut self.lines {
+            let line = strip_bom(line, i);
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Some(result) = self.builder.parse_line(line, i + 1).transpose() {
+                return Some(result);
+            }
+        }
+        None
+    }
+}
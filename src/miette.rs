@@ -0,0 +1,124 @@
+use crate::{error::ParseError, first_error};
+use ::miette::{Diagnostic, LabeledSpan, NamedSource, SourceCode, SourceSpan};
+use std::fmt::Display;
+
+/// Bare parse errors carry no source, so only a code and a help message are provided
+///
+/// Use [`SourceError`] to point at the line the error occurred on.
+///
+/// ```
+/// use miette::Diagnostic;
+/// use nescookie::error::ParseError;
+///
+/// let code = ParseError::TooFewFileds.code().unwrap();
+/// assert_eq!(code.to_string(), "nescookie::too_few_fields");
+/// ```
+impl Diagnostic for ParseError {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        let code = match self {
+            Self::InvaildValue(_) => "nescookie::invalid_value",
+            Self::TooFewFileds => "nescookie::too_few_fields",
+            Self::DuplicateName(_) => "nescookie::duplicate_name",
+            Self::InvalidName { .. } => "nescookie::invalid_name",
+            Self::UnexpectedComment { .. } => "nescookie::unexpected_comment",
+            Self::LineTooLong { .. } => "nescookie::line_too_long",
+            Self::WhitespaceLine => "nescookie::whitespace_line",
+        };
+        Some(Box::new(code))
+    }
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        Some(Box::new(self.hint()))
+    }
+}
+
+/// A [`ParseError`] together with the source it occurred in, labelling the offending line
+///
+/// ```
+/// use miette::Diagnostic;
+/// use nescookie::SourceError;
+///
+/// let content = ".pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7\n.pixiv.net	TRUE\n";
+/// let e = SourceError::first("cookies.txt", content).unwrap();
+/// assert_eq!(e.line(), 2);
+/// assert_eq!(e.labels().unwrap().next().unwrap().offset(), 44);
+/// ```
+#[derive(Debug)]
+pub struct SourceError {
+    error: ParseError,
+    line: usize,
+    src: NamedSource<String>,
+    span: SourceSpan,
+}
+
+impl SourceError {
+    /// Locates `error`, which occurred on the 1-based `line` of `source` named `name`
+    ///
+    /// The span covers the whole line, without its line break.
+    pub fn new(
+        name: impl AsRef<str>,
+        source: impl Into<String>,
+        line: usize,
+        error: ParseError,
+    ) -> Self {
+        let source = source.into();
+        let mut offset = 0;
+        let mut len = 0;
+        for (i, l) in source.split_inclusive('\n').enumerate() {
+            if i + 1 == line {
+                let l = l.strip_suffix('\n').unwrap_or(l);
+                len = l.strip_suffix('\r').unwrap_or(l).len();
+                break;
+            }
+            offset += l.len();
+        }
+        Self {
+            error,
+            line,
+            src: NamedSource::new(name, source),
+            span: SourceSpan::from((offset, len)),
+        }
+    }
+    /// Parses `source` and locates its first error, returning `None` if every line parses
+    pub fn first(name: impl AsRef<str>, source: &str) -> Option<Self> {
+        first_error(source).map(|(line, error)| Self::new(name, source, line, error))
+    }
+    /// Returns the error
+    pub fn error(&self) -> &ParseError {
+        &self.error
+    }
+    /// Returns the 1-based line the error occurred on
+    pub fn line(&self) -> usize {
+        self.line
+    }
+    /// Returns the span of the line the error occurred on
+    pub fn span(&self) -> SourceSpan {
+        self.span
+    }
+}
+
+impl Display for SourceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.error.fmt(f)
+    }
+}
+
+impl std::error::Error for SourceError {}
+
+impl Diagnostic for SourceError {
+    fn code<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        self.error.code()
+    }
+    fn help<'a>(&'a self) -> Option<Box<dyn Display + 'a>> {
+        self.error.help()
+    }
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.src)
+    }
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let label = format!("line {}", self.line);
+        Some(Box::new(std::iter::once(LabeledSpan::new_with_span(
+            Some(label),
+            self.span,
+        ))))
+    }
+}
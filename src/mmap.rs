@@ -0,0 +1,24 @@
+use crate::{error::Error, CookieJarBuilder};
+use memmap2::Mmap;
+use std::{fs::File, path::Path};
+
+impl CookieJarBuilder {
+    /// Memory-maps the file with `path` and parses it as cookies
+    ///
+    /// The mapped bytes are parsed in place with [`parse_bytes`](Self::parse_bytes),
+    /// so large files are not read into a `String` first.
+    /// The file must not be modified while it is being parsed.
+    ///
+    /// ```
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let jar = CookieJarBuilder::new().open_mmap("tests/cookies.txt").unwrap().finish();
+    /// ```
+    pub fn open_mmap(self, path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let file = File::open(path).map_err(Error::open(path))?;
+        // SAFETY: the map is only read, and lives no longer than this call
+        let map = unsafe { Mmap::map(&file)? };
+        self.parse_bytes(&map)
+    }
+}
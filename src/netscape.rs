@@ -0,0 +1,74 @@
+use crate::{error::Error, parse};
+use cookie::CookieJar;
+use std::{ops::Deref, str::FromStr};
+
+/// A [`CookieJar`](cookie::CookieJar) parsed from the netscape cookie format
+///
+/// ```
+/// use nescookie::NetscapeCookies;
+///
+/// let content = ".pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7\n";
+/// let cookies: NetscapeCookies = content.parse().unwrap();
+/// assert_eq!(cookies.get("p_ab_id").unwrap().value(), "7");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct NetscapeCookies(pub CookieJar);
+
+impl NetscapeCookies {
+    /// Returns the inner `CookieJar`
+    pub fn into_inner(self) -> CookieJar {
+        self.0
+    }
+}
+
+impl Deref for NetscapeCookies {
+    type Target = CookieJar;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl From<NetscapeCookies> for CookieJar {
+    fn from(cookies: NetscapeCookies) -> Self {
+        cookies.0
+    }
+}
+
+impl FromStr for NetscapeCookies {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse(s).map(Self)
+    }
+}
+
+/// ```
+/// use nescookie::NetscapeCookies;
+///
+/// let content = ".pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7\n";
+/// let cookies = NetscapeCookies::try_from(content).unwrap();
+/// assert_eq!(cookies.iter().count(), 1);
+/// ```
+impl TryFrom<&str> for NetscapeCookies {
+    type Error = Error;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// ```
+/// use nescookie::NetscapeCookies;
+///
+/// let content = String::from(".pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7\n");
+/// let cookies = NetscapeCookies::try_from(content).unwrap();
+/// assert_eq!(cookies.iter().count(), 1);
+/// ```
+impl TryFrom<String> for NetscapeCookies {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
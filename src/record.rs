@@ -0,0 +1,120 @@
+use crate::writer::Case;
+use cookie::{Cookie, CookieJar};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+};
+
+/// A cookie in netscape terms, with one field for each column of a cookies.txt line
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Record {
+    pub domain: String,
+    pub include_subdomains: bool,
+    pub path: String,
+    pub secure: bool,
+    /// Unix timestamp of the expiration, `0` for session cookies
+    pub expiration: i64,
+    pub name: String,
+    pub value: String,
+    pub http_only: bool,
+}
+
+/// Decomposes a [`Cookie`](cookie::Cookie) into a [`Record`]
+///
+/// `include_subdomains` is derived from a leading dot in the domain.
+/// A missing path is treated as `/`.
+///
+/// ```
+/// let jar = nescookie::open("tests/cookies.txt").unwrap();
+/// let record = nescookie::to_record(jar.get("PHPSESSID").unwrap());
+/// assert!(record.include_subdomains);
+/// assert!(record.http_only);
+/// assert_eq!(record.expiration, 1626662932);
+/// ```
+pub fn to_record(cookie: &Cookie) -> Record {
+    let domain = cookie.domain().unwrap_or_default();
+    Record {
+        domain: domain.to_owned(),
+        include_subdomains: domain.starts_with('.'),
+        path: cookie.path().unwrap_or("/").to_owned(),
+        secure: cookie.secure() == Some(true),
+        expiration: cookie.expires_datetime().map_or(0, |t| t.unix_timestamp()),
+        name: cookie.name().to_owned(),
+        value: cookie.value().to_owned(),
+        http_only: cookie.http_only() == Some(true),
+    }
+}
+
+/// Decomposes every cookie in `jar` into a [`Record`], sorted by domain, path and name
+///
+/// ```
+/// let jar = nescookie::open("tests/cookies.txt").unwrap();
+/// let rows = nescookie::rows(&jar);
+/// assert_eq!(rows[0].name, "PHPSESSID");
+/// ```
+pub fn rows(jar: &CookieJar) -> Vec<Record> {
+    let mut rows: Vec<_> = jar.iter().map(to_record).collect();
+    rows.sort_by(|a, b| (&a.domain, &a.path, &a.name).cmp(&(&b.domain, &b.path, &b.name)));
+    rows
+}
+
+/// Lists the names that appear under more than one domain in `records`, with the domains involved
+///
+/// Since a [`CookieJar`](cookie::CookieJar) keeps one cookie for each name, this works on records
+/// collected before they are added to a jar, for example from [`parse_results`](crate::parse_results).
+/// Names and domains are sorted.
+///
+/// ```
+/// let content = ".pixiv.net	TRUE	/	TRUE	0	a	1\nwww.pixiv.net	FALSE	/	TRUE	0	a	2\n";
+/// let records: Vec<_> = nescookie::parse_results(content)
+///     .iter()
+///     .flatten()
+///     .map(nescookie::to_record)
+///     .collect();
+/// let collisions = nescookie::name_collisions(&records);
+/// assert_eq!(collisions[0].1, [".pixiv.net", "www.pixiv.net"]);
+/// ```
+pub fn name_collisions(records: &[Record]) -> Vec<(String, Vec<String>)> {
+    let mut domains: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+    for r in records {
+        domains.entry(&r.name).or_default().insert(&r.domain);
+    }
+    domains
+        .into_iter()
+        .filter(|(_, d)| d.len() > 1)
+        .map(|(name, d)| (name.to_owned(), d.into_iter().map(str::to_owned).collect()))
+        .collect()
+}
+
+impl Record {
+    /// Formats the record as a cookies.txt line with flags spelled in `case`, without the line break
+    pub(crate) fn to_line(&self, case: Case) -> String {
+        format!(
+            "{}{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            if self.http_only { "#HttpOnly_" } else { "" },
+            self.domain,
+            flag(self.include_subdomains, case),
+            self.path,
+            flag(self.secure, case),
+            self.expiration,
+            self.name,
+            self.value
+        )
+    }
+}
+
+impl Display for Record {
+    /// Formats the record as a cookies.txt line, without the line break
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_line(Case::Upper))
+    }
+}
+
+fn flag(value: bool, case: Case) -> &'static str {
+    match (value, case) {
+        (true, Case::Upper) => "TRUE",
+        (false, Case::Upper) => "FALSE",
+        (true, Case::Lower) => "true",
+        (false, Case::Lower) => "false",
+    }
+}
@@ -0,0 +1,69 @@
+use crate::{error::Error, record::to_record};
+use cookie::{CookieJar, SameSite};
+use rusqlite::{params, Connection};
+use time::OffsetDateTime;
+
+/// Inserts the cookies of `jar` into the `moz_cookies` table of a Firefox `cookies.sqlite` database,
+/// returning how many were written
+///
+/// The table is created if it does not exist, and rows with the same name, host and path are replaced.
+/// `expiry` is written in seconds, while `lastAccessed` and `creationTime` are set to the current time
+/// in microseconds. Session cookies are written with an expiry of `0`.
+///
+/// ```
+/// use rusqlite::Connection;
+///
+/// let jar = nescookie::open("tests/cookies.txt").unwrap();
+/// let conn = Connection::open_in_memory().unwrap();
+/// assert_eq!(nescookie::to_sqlite(&jar, &conn).unwrap(), 6);
+/// ```
+pub fn to_sqlite(jar: &CookieJar, conn: &Connection) -> Result<usize, Error> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS moz_cookies (
+            id INTEGER PRIMARY KEY,
+            originAttributes TEXT NOT NULL DEFAULT '',
+            name TEXT,
+            value TEXT,
+            host TEXT,
+            path TEXT,
+            expiry INTEGER,
+            lastAccessed INTEGER,
+            creationTime INTEGER,
+            isSecure INTEGER,
+            isHttpOnly INTEGER,
+            inBrowserElement INTEGER DEFAULT 0,
+            sameSite INTEGER DEFAULT 0,
+            rawSameSite INTEGER DEFAULT 0,
+            schemeMap INTEGER DEFAULT 0,
+            CONSTRAINT moz_uniqueid UNIQUE (name, host, path, originAttributes)
+        )",
+    )?;
+    let now = (OffsetDateTime::now_utc().unix_timestamp_nanos() / 1000) as i64;
+    let mut stmt = conn.prepare(
+        "INSERT OR REPLACE INTO moz_cookies
+            (name, value, host, path, expiry, lastAccessed, creationTime, isSecure, isHttpOnly, sameSite, rawSameSite)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?6, ?7, ?8, ?9, ?9)",
+    )?;
+    let mut written = 0;
+    for c in jar.iter() {
+        let r = to_record(c);
+        // nsICookie::SAMESITE_NONE, SAMESITE_LAX and SAMESITE_STRICT
+        let same_site = match c.same_site() {
+            Some(SameSite::Lax) => 1,
+            Some(SameSite::Strict) => 2,
+            Some(SameSite::None) | None => 0,
+        };
+        written += stmt.execute(params![
+            r.name,
+            r.value,
+            r.domain,
+            r.path,
+            r.expiration,
+            now,
+            r.secure,
+            r.http_only,
+            same_site,
+        ])?;
+    }
+    Ok(written)
+}
@@ -0,0 +1,66 @@
+use crate::{error::Error, CookieJarBuilder};
+use cookie::CookieJar;
+use futures::{TryStream, TryStreamExt};
+use std::io;
+
+impl CookieJarBuilder {
+    /// Parses cookies from a stream of byte chunks, such as an HTTP response body
+    ///
+    /// Complete lines are parsed with [`parse_chunk`](Self::parse_chunk) as chunks arrive,
+    /// while a line split across chunks is buffered until its end is received.
+    /// An error of the stream stops parsing and is returned as an [`Error::IoError`].
+    ///
+    /// ```
+    /// use futures::{executor::block_on, stream};
+    /// use nescookie::CookieJarBuilder;
+    ///
+    /// let chunks = [&b".pixiv.net	TRUE	/	TRUE	1784339332	p_ab"[..], b"_id	7\n"];
+    /// let chunks = stream::iter(chunks.map(Ok::<_, std::io::Error>));
+    /// let jar = block_on(CookieJarBuilder::new().parse_stream(chunks)).unwrap().finish();
+    /// assert_eq!(jar.get("p_ab_id").unwrap().value(), "7");
+    /// ```
+    pub async fn parse_stream<S>(mut self, stream: S) -> Result<Self, Error>
+    where
+        S: TryStream,
+        S::Ok: AsRef<[u8]>,
+        S::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        let mut stream = std::pin::pin!(stream.into_stream());
+        let mut buf = Vec::new();
+        while let Some(chunk) = stream.try_next().await.map_err(io::Error::other)? {
+            buf.extend_from_slice(chunk.as_ref());
+            // a line break never occurs inside a multi-byte UTF-8 sequence
+            if let Some(i) = buf.iter().rposition(|&b| b == b'\n') {
+                let complete: Vec<_> = buf.drain(..=i).collect();
+                self.parse_chunk(utf8(&complete)?)?;
+            }
+        }
+        self.finish_chunks(utf8(&buf)?)?;
+        Ok(self)
+    }
+}
+
+fn utf8(bytes: &[u8]) -> Result<&str, Error> {
+    std::str::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e).into())
+}
+
+/// Parses a [`CookieJar`](cookie::CookieJar) from a stream of byte chunks
+///
+/// ```
+/// use futures::{executor::block_on, stream};
+///
+/// let chunks = stream::iter([Ok::<_, std::io::Error>(b".pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7\n")]);
+/// let jar = block_on(nescookie::parse_stream(chunks)).unwrap();
+/// ```
+#[inline]
+pub async fn parse_stream<S>(stream: S) -> Result<CookieJar, Error>
+where
+    S: TryStream,
+    S::Ok: AsRef<[u8]>,
+    S::Error: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    CookieJarBuilder::new()
+        .parse_stream(stream)
+        .await
+        .map(|jar| jar.finish())
+}
@@ -0,0 +1,40 @@
+use crate::{error::Error, jar::domain_matches};
+use ::url::Url;
+use cookie::{Cookie, CookieJar};
+
+/// Returns the cookies in `jar` that would be sent to `url`
+///
+/// A cookie applies if its domain matches the host, its path is a prefix of the
+/// url path as described in RFC 6265, and it is not secure unless the url is `https` or `wss`.
+/// Cookies without a domain are skipped.
+///
+/// ```
+/// let jar = nescookie::open("tests/cookies.txt").unwrap();
+/// let cookies = nescookie::cookies_for_url(&jar, "https://www.pixiv.net/ranking").unwrap();
+/// assert_eq!(cookies.len(), 6);
+/// ```
+pub fn cookies_for_url<'a>(
+    jar: &'a CookieJar,
+    url: &str,
+) -> Result<Vec<&'a Cookie<'static>>, Error> {
+    let url = Url::parse(url).map_err(|e| Error::InvalidUrl(Box::new(e)))?;
+    let host = match url.host_str() {
+        Some(host) => host,
+        None => return Ok(Vec::new()),
+    };
+    let secure = matches!(url.scheme(), "https" | "wss");
+    Ok(jar
+        .iter()
+        .filter(|c| matches!(c.domain(), Some(d) if domain_matches(d, host)))
+        .filter(|c| path_matches(c.path().unwrap_or("/"), url.path()))
+        .filter(|c| secure || c.secure() != Some(true))
+        .collect())
+}
+
+/// Checks whether a cookie with `cookie_path` applies to `path`
+fn path_matches(cookie_path: &str, path: &str) -> bool {
+    match path.strip_prefix(cookie_path) {
+        Some(rest) => rest.is_empty() || cookie_path.ends_with('/') || rest.starts_with('/'),
+        None => false,
+    }
+}
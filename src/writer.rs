@@ -0,0 +1,203 @@
+use crate::{error::Error, record::to_record};
+use cookie::CookieJar;
+use std::{
+    fs::OpenOptions,
+    io::{Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+use time::OffsetDateTime;
+
+pub(crate) const HEADER: &str = "# Netscape HTTP Cookie File";
+
+/// The flavour of cookies.txt written by [`CookieJarWriter`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Dialect {
+    /// The format of browser extensions, with a single header line
+    #[default]
+    Browser,
+    /// The format of `curl --cookie-jar`
+    ///
+    /// The header is followed by the lines curl writes and a blank line,
+    /// and cookies without a domain are left out since curl cannot use them.
+    Curl,
+}
+
+/// How [`CookieJarWriter`] spells the boolean fields
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Case {
+    /// `TRUE` and `FALSE`
+    #[default]
+    Upper,
+    /// `true` and `false`
+    Lower,
+}
+
+/// A netscape cookie writer
+/// serializing a [`CookieJar`](cookie::CookieJar) into the cookies.txt format.
+///
+/// Cookies are written sorted by domain, path and name.
+#[derive(Debug, Clone)]
+pub struct CookieJarWriter {
+    trailing_newline: bool,
+    now: Option<OffsetDateTime>,
+    dialect: Dialect,
+    bool_case: Case,
+}
+
+impl Default for CookieJarWriter {
+    fn default() -> Self {
+        Self {
+            trailing_newline: true,
+            now: None,
+            dialect: Dialect::default(),
+            bool_case: Case::default(),
+        }
+    }
+}
+
+impl CookieJarWriter {
+    /// Creates a new `CookieJarWriter`
+    /// ```
+    /// use nescookie::CookieJarWriter;
+    ///
+    /// let writer = CookieJarWriter::new();
+    /// ```
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Sets whether the last line ends with a newline
+    ///
+    /// Defaults to `true`, matching the output of curl.
+    ///
+    /// ```
+    /// use nescookie::CookieJarWriter;
+    ///
+    /// let jar = nescookie::parse(".pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7\n").unwrap();
+    /// let s = CookieJarWriter::new().trailing_newline(false).write_string(&jar);
+    /// assert!(s.ends_with("p_ab_id\t7"));
+    /// ```
+    pub fn trailing_newline(mut self, trailing_newline: bool) -> Self {
+        self.trailing_newline = trailing_newline;
+        self
+    }
+    /// Omits cookies that have expired at `now`
+    ///
+    /// Session cookies are always written.
+    ///
+    /// ```
+    /// use nescookie::{CookieJarWriter, OffsetDateTime};
+    ///
+    /// let jar = nescookie::open("tests/cookies.txt").unwrap();
+    /// let s = CookieJarWriter::new()
+    ///     .skip_expired(OffsetDateTime::from_unix_timestamp(1700000000))
+    ///     .write_string(&jar);
+    /// assert!(!s.contains("PHPSESSID"));
+    /// ```
+    pub fn skip_expired(mut self, now: OffsetDateTime) -> Self {
+        self.now = Some(now);
+        self
+    }
+    /// Sets the flavour of the output
+    ///
+    /// ```
+    /// use nescookie::{CookieJarWriter, Dialect};
+    ///
+    /// let jar = nescookie::open("tests/cookies.txt").unwrap();
+    /// let s = CookieJarWriter::new().dialect(Dialect::Curl).write_string(&jar);
+    /// assert!(s.contains("# This file was generated by libcurl! Edit at your own risk.\n\n"));
+    /// ```
+    pub fn dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+    /// Sets the spelling of the subdomain and secure fields
+    ///
+    /// Defaults to [`Case::Upper`]. Files with lowercase booleans can be parsed with
+    /// [`BoolStyle::Lenient`](crate::BoolStyle::Lenient) and written back with [`Case::Lower`].
+    ///
+    /// ```
+    /// use nescookie::{Case, CookieJarWriter};
+    ///
+    /// let jar = nescookie::parse(".pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7\n").unwrap();
+    /// let s = CookieJarWriter::new().bool_case(Case::Lower).write_string(&jar);
+    /// assert!(s.ends_with(".pixiv.net\ttrue\t/\ttrue\t1784339332\tp_ab_id\t7\n"));
+    /// ```
+    pub fn bool_case(mut self, case: Case) -> Self {
+        self.bool_case = case;
+        self
+    }
+    /// Writes `jar` to `w`
+    pub fn write(&self, jar: &CookieJar, mut w: impl Write) -> Result<(), Error> {
+        w.write_all(self.write_string(jar).as_bytes())?;
+        Ok(())
+    }
+    /// Writes `jar` to a `String`
+    pub fn write_string(&self, jar: &CookieJar) -> String {
+        let mut lines = vec![HEADER.to_owned()];
+        if self.dialect == Dialect::Curl {
+            lines.push("# https://curl.se/docs/http-cookies.html".to_owned());
+            lines.push("# This file was generated by libcurl! Edit at your own risk.".to_owned());
+            lines.push(String::new());
+        }
+        lines.extend(self.records(jar));
+        let mut s = lines.join("\n");
+        if self.trailing_newline {
+            s.push('\n');
+        }
+        s
+    }
+    /// Appends the cookies of `jar` to the file at `path` without rewriting it
+    ///
+    /// The header is only written if the file is empty or does not exist yet.
+    ///
+    /// ```no_run
+    /// use nescookie::CookieJarWriter;
+    ///
+    /// let jar = nescookie::parse(".pixiv.net	TRUE	/	TRUE	1784339332	p_ab_id	7\n").unwrap();
+    /// CookieJarWriter::new().append(&jar, "cookies.txt").unwrap();
+    /// ```
+    pub fn append(&self, jar: &CookieJar, path: impl AsRef<Path>) -> Result<(), Error> {
+        let path = path.as_ref();
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)
+            .map_err(Error::open(path))?;
+        if file.metadata()?.len() == 0 {
+            return self.write(jar, file);
+        }
+        let lines = self.records(jar);
+        if lines.is_empty() {
+            return Ok(());
+        }
+        let mut s = String::new();
+        let mut last = [0];
+        file.seek(SeekFrom::End(-1))?;
+        file.read_exact(&mut last)?;
+        if last != *b"\n" {
+            s.push('\n');
+        }
+        s.push_str(&lines.join("\n"));
+        if self.trailing_newline {
+            s.push('\n');
+        }
+        file.write_all(s.as_bytes())?;
+        Ok(())
+    }
+    /// Returns the lines of the cookies in `jar` to write, sorted by domain, path and name
+    pub(crate) fn records(&self, jar: &CookieJar) -> Vec<String> {
+        let mut cookies: Vec<_> = jar
+            .iter()
+            .filter(|c| !matches!((self.now, c.expires_datetime()), (Some(now), Some(exp)) if exp <= now))
+            .filter(|c| self.dialect != Dialect::Curl || c.domain().is_some())
+            .collect();
+        cookies.sort_by(|a, b| {
+            (a.domain(), a.path(), a.name()).cmp(&(b.domain(), b.path(), b.name()))
+        });
+        cookies
+            .into_iter()
+            .map(|c| to_record(c).to_line(self.bool_case))
+            .collect()
+    }
+}
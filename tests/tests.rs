@@ -1,8 +1,21 @@
 #[cfg(test)]
 mod tests {
-    use nescookie::parse;
+    use nescookie::{
+        add_cookie_header, debug_dump,
+        error::{BinaryError, Error, ParseError},
+        estimate_bytes, expiring_within, find_by_value, fingerprint, first_error, from_bytes,
+        has_domain, http_only_cookies, min_expiry, name_collisions, pairs, parse, parse_bytes,
+        parse_expecting, parse_head, parse_into, parse_results, parse_streaming, partition_secure,
+        prune_expired, redact, rows, seconds_until_expiry, set_expiry, set_value, sorted_by_expiry,
+        split_by_domain, to_bytes, to_record, validate, BoolStyle, Case, Cookie, CookieJarBuilder,
+        CookieJarWriter, CookieLines, Dialect, DuplicatePolicy, ErrorMode, OffsetDateTime,
+        ParseMetrics, Record, SameSite,
+    };
+    use proptest::prelude::*;
+    use std::{ops::ControlFlow, path::Path, time::Duration};
     const CONTENT: &str = include_str!("cookies.txt");
     #[test]
+    #[allow(clippy::map_flatten)]
     fn cookie() {
         let jar = parse(CONTENT).unwrap();
         assert_eq!(jar.iter().count(), 6);
@@ -28,4 +41,2182 @@ mod tests {
         );
         assert_eq!(jar.get("yuid_b").map(|c| c.path()).flatten(), Some("/"))
     }
+    #[test]
+    fn bytes_round_trip() {
+        let jar = parse(CONTENT).unwrap();
+        let decoded = from_bytes(&to_bytes(&jar)).unwrap();
+        assert_eq!(decoded.iter().count(), 6);
+        for c in jar.iter() {
+            let d = decoded.get(c.name()).unwrap();
+            assert_eq!(c.value(), d.value());
+            assert_eq!(c.domain(), d.domain());
+            assert_eq!(c.path(), d.path());
+            assert_eq!(c.secure(), d.secure());
+            assert_eq!(c.http_only(), d.http_only());
+            assert_eq!(c.expires_datetime(), d.expires_datetime());
+        }
+        assert!(matches!(
+            from_bytes(&to_bytes(&jar)[..10]),
+            Err(Error::Binary(BinaryError::UnexpectedEnd))
+        ));
+        assert!(matches!(
+            from_bytes(b"NES\x02"),
+            Err(Error::Binary(BinaryError::InvalidHeader))
+        ));
+        let mut bytes = b"NES\x01\x01\0\0\0\xff".to_vec();
+        assert!(matches!(
+            from_bytes(&bytes),
+            Err(Error::Binary(BinaryError::InvalidUtf8))
+        ));
+        bytes[8] = b'a';
+        bytes.extend_from_slice(&[0; 12]);
+        bytes.push(0);
+        bytes.extend_from_slice(&i64::MAX.to_le_bytes());
+        assert!(matches!(
+            from_bytes(&bytes),
+            Err(Error::Binary(BinaryError::InvalidExpiration(i64::MAX)))
+        ));
+    }
+    #[test]
+    fn empty_value() {
+        let trailing_tab = ".pixiv.net\tTRUE\t/\tTRUE\t1784339332\tp_ab_id\t\n";
+        let jar = parse(trailing_tab).unwrap();
+        assert_eq!(jar.get("p_ab_id").map(|c| c.value()), Some(""));
+
+        let no_tab = ".pixiv.net\tTRUE\t/\tTRUE\t1784339332\tp_ab_id\n";
+        assert!(parse(no_tab).is_err());
+        let jar = CookieJarBuilder::new()
+            .allow_missing_value(true)
+            .parse(no_tab)
+            .unwrap()
+            .finish();
+        assert_eq!(jar.get("p_ab_id").map(|c| c.value()), Some(""));
+    }
+    #[test]
+    fn provenance() {
+        let builder = CookieJarBuilder::new()
+            .open_many(["tests/cookies.txt", "benches/cookies.txt"])
+            .unwrap();
+        assert_eq!(
+            builder.source_of("p_ab_id"),
+            Some(Path::new("tests/cookies.txt"))
+        );
+        assert_eq!(
+            builder.source_of("b3erjt1byk"),
+            Some(Path::new("benches/cookies.txt"))
+        );
+        let builder = builder.parse(CONTENT).unwrap();
+        assert_eq!(builder.source_of("p_ab_id"), None);
+        assert_eq!(builder.source_of("missing"), None);
+    }
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn chrono_expiration() {
+        use chrono::{TimeZone, Utc};
+        use nescookie::{expires_chrono, from_chrono, to_chrono};
+        let jar = parse(CONTENT).unwrap();
+        let cookie = jar.get("PHPSESSID").unwrap();
+        let exp = expires_chrono(cookie).unwrap();
+        assert_eq!(exp.timestamp(), 1626662932);
+        assert_eq!(from_chrono(exp), cookie.expires_datetime());
+        assert_eq!(to_chrono(from_chrono(exp).unwrap()), Some(exp));
+        let far = Utc.with_ymd_and_hms(20000, 1, 1, 0, 0, 0).unwrap();
+        assert_eq!(from_chrono(far), None);
+
+        let jar = CookieJarBuilder::new()
+            .skip_expired_chrono(exp)
+            .parse(CONTENT)
+            .unwrap()
+            .finish();
+        assert!(jar.get("PHPSESSID").is_none());
+        assert_eq!(jar.iter().count(), 5);
+
+        // a time out of range is clamped instead of panicking
+        let jar = CookieJarBuilder::new()
+            .skip_expired_chrono(far)
+            .parse(CONTENT)
+            .unwrap()
+            .finish();
+        assert_eq!(jar.iter().count(), 0);
+    }
+    #[test]
+    fn prune() {
+        let content = ".pixiv.net\tTRUE\t/\tTRUE\t0\tsession\t1
+.pixiv.net\tTRUE\t/\tTRUE\t1000\told\t2
+.pixiv.net\tTRUE\t/\tTRUE\t2000\tedge\t3
+.pixiv.net\tTRUE\t/\tTRUE\t3000\tfresh\t4
+";
+        let mut jar = parse(content).unwrap();
+        let now = OffsetDateTime::from_unix_timestamp(2000);
+        assert_eq!(prune_expired(&mut jar, now), 2);
+        assert_eq!(jar.iter().count(), 2);
+        assert!(jar.get("session").is_some());
+        assert!(jar.get("fresh").is_some());
+        assert_eq!(prune_expired(&mut jar, now), 0);
+    }
+    #[test]
+    fn http_only_prefix_whitespace() {
+        let content = "  #HttpOnly_ .pixiv.net\tTRUE\t/\tTRUE\t1626662932\tPHPSESSID\tj6amv2\n";
+        let jar = parse(content).unwrap();
+        let cookie = jar.get("PHPSESSID").unwrap();
+        assert_eq!(cookie.domain(), Some(".pixiv.net"));
+        assert_eq!(cookie.http_only(), Some(true));
+    }
+    #[test]
+    fn results() {
+        let content = "# comment
+.pixiv.net\tTRUE\t/\tTRUE\t1784339332\tp_ab_id\t7
+
+.pixiv.net\tTRUE\t/\tMAYBE\t1784339332\tp_ab_id_2\t9
+.pixiv.net\tTRUE\t/
+.pixiv.net\tTRUE\t/\tTRUE\t1784339332\tp_ab_d_id\t620724492
+";
+        let results = parse_results(content);
+        assert_eq!(results.len(), 4);
+        assert_eq!(results[0].as_ref().unwrap().name(), "p_ab_id");
+        assert_eq!(
+            results[1].as_ref().unwrap_err(),
+            &(4, ParseError::InvaildValue("MAYBE".to_owned()))
+        );
+        assert_eq!(
+            results[2].as_ref().unwrap_err(),
+            &(5, ParseError::TooFewFileds)
+        );
+        assert_eq!(results[3].as_ref().unwrap().name(), "p_ab_d_id");
+    }
+    #[test]
+    fn duplicates() {
+        let content = ".pixiv.net\tTRUE\t/\tTRUE\t0\tPHPSESSID\tfirst
+www.pixiv.net\tFALSE\t/\tTRUE\t0\tPHPSESSID\tsecond
+";
+        let parse_with = |policy| {
+            CookieJarBuilder::new()
+                .duplicate_policy(policy)
+                .parse(content)
+                .map(|b| b.finish())
+        };
+        let jar = parse_with(DuplicatePolicy::Overwrite).unwrap();
+        assert_eq!(jar.get("PHPSESSID").map(|c| c.value()), Some("second"));
+        let jar = parse_with(DuplicatePolicy::KeepFirst).unwrap();
+        assert_eq!(jar.get("PHPSESSID").map(|c| c.value()), Some("first"));
+        assert!(matches!(
+            parse_with(DuplicatePolicy::Error),
+            Err(Error::ParseError(ParseError::DuplicateName(name))) if name == "PHPSESSID"
+        ));
+    }
+    #[test]
+    fn dump() {
+        let content = ".pixiv.net\tTRUE\t/\tTRUE\t0\tsession\t1
+.pixiv.net\tTRUE\t/\tTRUE\t1626662932\tpersistent\t2
+";
+        let dump = debug_dump(&parse(content).unwrap());
+        let lines: Vec<_> = dump.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("persistent=2"));
+        assert!(lines[0].ends_with("expires=2021-07-19T02:48:52+00:00"));
+        assert!(lines[1].starts_with("session=1"));
+        assert!(lines[1].ends_with("expires=session"));
+    }
+    #[cfg(feature = "zip")]
+    #[test]
+    fn zip_entry() {
+        let jar = CookieJarBuilder::new()
+            .open_zip("tests/cookies.zip", "cookies.txt")
+            .unwrap()
+            .finish();
+        assert_eq!(jar.iter().count(), 6);
+        assert!(matches!(
+            CookieJarBuilder::new().open_zip("tests/cookies.zip", "missing.txt"),
+            Err(Error::Archive(_))
+        ));
+    }
+    #[test]
+    fn rename() {
+        let jar = CookieJarBuilder::new()
+            .rename(|name| format!("p1_{}", name))
+            .parse(CONTENT)
+            .unwrap()
+            .finish();
+        assert_eq!(jar.iter().count(), 6);
+        assert!(jar.get("PHPSESSID").is_none());
+        assert_eq!(jar.get("p1_p_ab_id").map(|c| c.value()), Some("7"));
+        assert_eq!(
+            jar.get("p1_PHPSESSID").and_then(|c| c.http_only()),
+            Some(true)
+        );
+    }
+    #[test]
+    fn error_hints() {
+        let err = parse(".pixiv.net\tTRUE\t/\n").unwrap_err();
+        assert!(err.to_string().contains("expected 7 tab-separated fields"));
+        let err = parse(".pixiv.net\tTRUE\t/\tMAYBE\t0\ta\t1\n").unwrap_err();
+        assert!(err.to_string().contains("expected TRUE/FALSE"));
+        let err = nescookie::open("tests/missing.txt").unwrap_err();
+        assert!(matches!(&err, Error::Open { path, .. } if path == Path::new("tests/missing.txt")));
+        assert!(err.to_string().contains("check that the file is readable"));
+        assert!(err.to_string().contains("tests/missing.txt"));
+        // other io errors are not about files
+        let err = CookieJarBuilder::new()
+            .parse_buffer(&[0xff, b'\n'][..])
+            .unwrap_err();
+        assert!(matches!(err, Error::IoError(_)));
+        assert!(!err.to_string().contains("check that the file is readable"));
+    }
+    #[test]
+    fn swapped_columns() {
+        let content = ".pixiv.net\tTRUE\t/\t1784339332\tTRUE\tp_ab_id\t7\n";
+        assert!(parse(content).is_err());
+        let jar = CookieJarBuilder::new()
+            .tolerant_columns(true)
+            .parse(content)
+            .unwrap()
+            .finish();
+        let cookie = jar.get("p_ab_id").unwrap();
+        assert_eq!(cookie.secure(), Some(true));
+        assert_eq!(
+            cookie.expires_datetime().map(|t| t.unix_timestamp()),
+            Some(1784339332)
+        );
+        assert_eq!(cookie.value(), "7");
+    }
+    #[test]
+    fn estimate() {
+        let jar = parse(".pixiv.net\tTRUE\t/\tTRUE\t1784339332\tp_ab_id\t7\n").unwrap();
+        assert_eq!(estimate_bytes(&jar), 10 + 1 + 7 + 1);
+        let jar = parse(CONTENT).unwrap();
+        let estimate = estimate_bytes(&jar);
+        assert!(estimate > 0 && estimate < CONTENT.len());
+    }
+    #[test]
+    fn iso_expiration() {
+        let numeric = parse(".pixiv.net\tTRUE\t/\tTRUE\t1704067200\ta\t1\n").unwrap();
+        let iso = parse(".pixiv.net\tTRUE\t/\tTRUE\t2024-01-01T00:00:00Z\ta\t1\n").unwrap();
+        assert_eq!(
+            iso.get("a").and_then(|c| c.expires_datetime()),
+            Some(OffsetDateTime::from_unix_timestamp(1704067200))
+        );
+        assert_eq!(
+            numeric.get("a").and_then(|c| c.expires_datetime()),
+            iso.get("a").and_then(|c| c.expires_datetime())
+        );
+        assert!(parse(".pixiv.net\tTRUE\t/\tTRUE\t2024-01-01\ta\t1\n").is_err());
+    }
+    #[test]
+    fn out_of_range_expiration() {
+        assert!(parse(".pixiv.net\tTRUE\t/\tTRUE\t9223372036854775807\ta\t1\n").is_err());
+        assert!(parse(".pixiv.net\tTRUE\t/\tTRUE\t-9223372036854775808\ta\t1\n").is_err());
+    }
+    proptest! {
+        #[test]
+        fn parse_never_panics(s in "\\PC*") {
+            let _ = parse(&s);
+        }
+        #[test]
+        fn parse_bytes_never_panics(bytes in proptest::collection::vec(any::<u8>(), 0..256)) {
+            let _ = parse_bytes(&bytes);
+        }
+        #[test]
+        fn record_round_trip(
+            domain in "\\.?[a-z0-9]{1,10}(\\.[a-z]{2,5})?",
+            path in "/[a-z0-9/]{0,10}",
+            secure in any::<bool>(),
+            expiration in 0i64..253402300799,
+            name in "[A-Za-z0-9_]{1,16}",
+            value in "[A-Za-z0-9_%+.-]{0,16}",
+            http_only in any::<bool>(),
+        ) {
+            let line = format!(
+                "{}{}\tTRUE\t{}\t{}\t{}\t{}\t{}\n",
+                if http_only { "#HttpOnly_" } else { "" },
+                domain,
+                path,
+                if secure { "TRUE" } else { "FALSE" },
+                expiration,
+                name,
+                value
+            );
+            let jar = parse(&line).unwrap();
+            let cookie = jar.get(&name).unwrap();
+            prop_assert_eq!(cookie.domain(), Some(domain.as_str()));
+            prop_assert_eq!(cookie.path(), Some(path.as_str()));
+            prop_assert_eq!(cookie.secure(), Some(secure));
+            prop_assert_eq!(cookie.http_only(), http_only.then_some(true));
+            prop_assert_eq!(
+                cookie.expires_datetime().map(|t| t.unix_timestamp()),
+                (expiration != 0).then_some(expiration)
+            );
+            prop_assert_eq!(cookie.value(), value.as_str());
+        }
+    }
+    #[test]
+    fn value_pattern() {
+        let jar = parse(CONTENT).unwrap();
+        let found = find_by_value(&jar, |v| v.contains("%3A"));
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].name(), "first_visit_datetime_pc");
+        assert!(find_by_value(&jar, |v| v == "not there").is_empty());
+    }
+    #[test]
+    fn head() {
+        let jar = parse_head(CONTENT, 2).unwrap();
+        assert_eq!(jar.iter().count(), 2);
+        assert!(jar.get("first_visit_datetime_pc").is_some());
+        assert!(jar.get("PHPSESSID").is_some());
+        assert_eq!(parse_head(CONTENT, 0).unwrap().iter().count(), 0);
+        assert_eq!(parse_head(CONTENT, 100).unwrap().iter().count(), 6);
+    }
+    #[cfg(feature = "cookie_store")]
+    #[test]
+    fn cookie_store_round_trip() {
+        use cookie_store::CookieStore;
+        use nescookie::from_cookie_store;
+        use url::Url;
+
+        let content = ".pixiv.net\tTRUE\t/\tTRUE\t4102444800\tp_ab_id\t7
+#HttpOnly_www.pixiv.net\tFALSE\t/ranking\tFALSE\t4102444800\tPHPSESSID\tj6amv2
+";
+        let jar = parse(content).unwrap();
+        let mut store = CookieStore::default();
+        for c in jar.iter() {
+            let domain = c.domain().unwrap();
+            let mut set_cookie = format!(
+                "{}={}; Path={}; Expires=Fri, 01 Jan 2100 00:00:00 GMT",
+                c.name(),
+                c.value(),
+                c.path().unwrap()
+            );
+            if let Some(suffix) = domain.strip_prefix('.') {
+                set_cookie.push_str(&format!("; Domain={}", suffix));
+            }
+            if c.secure() == Some(true) {
+                set_cookie.push_str("; Secure");
+            }
+            if c.http_only() == Some(true) {
+                set_cookie.push_str("; HttpOnly");
+            }
+            let url = Url::parse(&format!("https://{}/", domain.trim_start_matches('.'))).unwrap();
+            store.parse(&set_cookie, &url).unwrap();
+        }
+        let converted = from_cookie_store(&store);
+        assert_eq!(converted.iter().count(), 2);
+        for c in jar.iter() {
+            let d = converted.get(c.name()).unwrap();
+            assert_eq!(c.value(), d.value());
+            assert_eq!(c.domain(), d.domain());
+            assert_eq!(c.path(), d.path());
+            assert_eq!(c.secure(), d.secure());
+            assert_eq!(c.http_only(), d.http_only());
+            assert_eq!(c.expires_datetime(), d.expires_datetime());
+        }
+    }
+    #[test]
+    fn clone_builder() {
+        let base = CookieJarBuilder::new().parse(CONTENT).unwrap();
+        let extended = base
+            .clone()
+            .parse(".pixiv.net\tTRUE\t/\tTRUE\t0\textra\t1\n")
+            .unwrap()
+            .finish();
+        let base = base.finish();
+        assert_eq!(base.iter().count(), 6);
+        assert!(base.get("extra").is_none());
+        assert_eq!(extended.iter().count(), 7);
+        assert!(extended.get("extra").is_some());
+    }
+    #[test]
+    fn bare() {
+        let jar = CookieJarBuilder::new()
+            .bare(true)
+            .parse(CONTENT)
+            .unwrap()
+            .finish();
+        assert_eq!(jar.iter().count(), 6);
+        for c in jar.iter() {
+            assert_eq!(c.domain(), None);
+            assert_eq!(c.path(), None);
+            assert_eq!(c.secure(), None);
+        }
+        assert_eq!(jar.get("p_ab_id").map(|c| c.value()), Some("7"));
+    }
+    #[test]
+    fn trailing_newline() {
+        let content = "# Netscape HTTP Cookie File
+#HttpOnly_.pixiv.net\tTRUE\t/\tTRUE\t1626662932\tPHPSESSID\tj6amv2
+www.pixiv.net\tFALSE\t/\tFALSE\t0\tyuid_b\tFBdWQEY
+";
+        let jar = parse(content).unwrap();
+        assert_eq!(CookieJarWriter::new().write_string(&jar), content);
+        assert_eq!(
+            CookieJarWriter::new()
+                .trailing_newline(false)
+                .write_string(&jar),
+            content.trim_end_matches('\n')
+        );
+        let mut buf = Vec::new();
+        nescookie::write(&jar, &mut buf).unwrap();
+        assert_eq!(buf, content.as_bytes());
+    }
+    #[test]
+    fn name_validation() {
+        let builder = || CookieJarBuilder::new().validate_names(true);
+        let jar = builder().parse(CONTENT).unwrap().finish();
+        assert_eq!(jar.iter().count(), 6);
+        for name in ["p ab id", "p_ab\u{1}id", "p;ab", "p_ab_\u{e9}"] {
+            let content = format!(".pixiv.net\tTRUE\t/\tTRUE\t0\t{}\t7\n", name);
+            assert!(matches!(
+                builder().parse(&format!("# comment\n{}", content)),
+                Err(Error::ParseError(ParseError::InvalidName { line: 2, name: n })) if n == name
+            ));
+            assert!(parse(&content).is_ok());
+        }
+    }
+    #[test]
+    fn force_flags() {
+        let content = format!(
+            "{}www.pixiv.net\tFALSE\t/\tFALSE\t0\tinsecure\t1\n",
+            CONTENT
+        );
+        let jar = CookieJarBuilder::new()
+            .force_secure(true)
+            .force_http_only(true)
+            .parse(&content)
+            .unwrap()
+            .finish();
+        assert_eq!(jar.iter().count(), 7);
+        assert!(jar.iter().all(|c| c.secure() == Some(true)));
+        assert!(jar.iter().all(|c| c.http_only() == Some(true)));
+
+        let jar = CookieJarBuilder::new()
+            .force_secure(false)
+            .force_http_only(false)
+            .parse(&content)
+            .unwrap()
+            .finish();
+        assert!(jar.iter().all(|c| c.secure() == Some(false)));
+        assert!(jar.iter().all(|c| c.http_only() != Some(true)));
+    }
+    #[test]
+    fn forced_path() {
+        let content = format!(
+            "{}www.pixiv.net\tFALSE\t/novel\tFALSE\t0\tnovel\t1\n",
+            CONTENT
+        );
+        let jar = CookieJarBuilder::new()
+            .force_path("/api")
+            .parse(&content)
+            .unwrap()
+            .finish();
+        assert_eq!(jar.iter().count(), 7);
+        assert!(jar.iter().all(|c| c.path() == Some("/api")));
+    }
+    #[test]
+    fn validation() {
+        assert_eq!(validate(CONTENT), Ok(()));
+        let corrupted = CONTENT
+            .replace("\t1784339332\tp_ab_id\t", "\tsoon\tp_ab_id\t")
+            .replace("\tFBdWQEY", "");
+        let errors = validate(&corrupted).unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0], (12, ParseError::InvaildValue("soon".to_owned())));
+        assert_eq!(errors[1], (15, ParseError::TooFewFileds));
+    }
+    #[test]
+    fn latest_expiry() {
+        let content = ".pixiv.net\tTRUE\t/\tTRUE\t2000000000\ta\tlater
+.pixiv.net\tTRUE\t/\tTRUE\t1000000000\ta\tearlier
+.pixiv.net\tTRUE\t/\tTRUE\t1000000000\tb\tearlier
+.pixiv.net\tTRUE\t/\tTRUE\t2000000000\tb\tlater
+.pixiv.net\tTRUE\t/\tTRUE\t0\tc\tsession
+.pixiv.net\tTRUE\t/\tTRUE\t2000000000\tc\tlater
+";
+        let jar = CookieJarBuilder::new()
+            .prefer_latest_expiry(true)
+            .parse(content)
+            .unwrap()
+            .finish();
+        assert_eq!(jar.get("a").map(|c| c.value()), Some("later"));
+        assert_eq!(jar.get("b").map(|c| c.value()), Some("later"));
+        assert_eq!(jar.get("c").map(|c| c.value()), Some("session"));
+        let jar = parse(content).unwrap();
+        assert_eq!(jar.get("a").map(|c| c.value()), Some("earlier"));
+    }
+    #[test]
+    fn record() {
+        let jar = parse(CONTENT).unwrap();
+        assert_eq!(
+            to_record(jar.get("first_visit_datetime_pc").unwrap()),
+            Record {
+                domain: "www.pixiv.net".to_owned(),
+                include_subdomains: false,
+                path: "/".to_owned(),
+                secure: true,
+                expiration: 1689731332,
+                name: "first_visit_datetime_pc".to_owned(),
+                value: "2021-07-19+10%3A48%3A50".to_owned(),
+                http_only: false,
+            }
+        );
+        let record = to_record(jar.get("PHPSESSID").unwrap());
+        assert!(record.include_subdomains);
+        assert!(record.http_only);
+        assert_eq!(
+            record.to_string(),
+            "#HttpOnly_.pixiv.net\tTRUE\t/\tTRUE\t1626662932\tPHPSESSID\tj6amv2igf0cec4fdtld5rre5ud7ig3l2"
+        );
+    }
+    #[cfg(feature = "encoding")]
+    #[test]
+    fn shift_jis() {
+        let jar = CookieJarBuilder::new()
+            .open_with_encoding("tests/cookies_sjis.txt", encoding_rs::SHIFT_JIS)
+            .unwrap()
+            .finish();
+        assert_eq!(jar.get("nickname").map(|c| c.value()), Some("ピクシブ太郎"));
+        assert!(nescookie::open("tests/cookies_sjis.txt").is_err());
+    }
+    #[test]
+    fn chunks() {
+        let mut builder = CookieJarBuilder::new();
+        let mut tail = String::new();
+        for chunk in [
+            "# Netscape HTTP Cookie File\n.pixiv.net\tTRUE\t/\tTRUE\t17843",
+            "39332\tp_ab_id\t7\n",
+            ".pixiv.net\tTRUE\t/\tTRUE\t1784339332\tlast\t1",
+        ] {
+            tail = builder.parse_chunk(&(tail + chunk)).unwrap();
+            assert!(builder.clone().finish().get("last").is_none());
+        }
+        assert_eq!(tail, ".pixiv.net\tTRUE\t/\tTRUE\t1784339332\tlast\t1");
+        builder.finish_chunks(&tail).unwrap();
+        let jar = builder.finish();
+        assert_eq!(jar.iter().count(), 2);
+        assert_eq!(
+            jar.get("p_ab_id").and_then(|c| c.expires_datetime()),
+            Some(OffsetDateTime::from_unix_timestamp(1784339332))
+        );
+
+        // line numbers continue across chunks
+        let mut builder = CookieJarBuilder::new().disallow_comments(true);
+        let tail = builder
+            .parse_chunk("# Netscape HTTP Cookie File\n")
+            .unwrap();
+        let tail = builder.parse_chunk(&(tail + ".pixiv.net\tTRUE\t/\tTRUE\t1784339332\ta\t1\n"));
+        let e = builder
+            .parse_chunk(&(tail.unwrap() + "# comment\n"))
+            .unwrap_err();
+        assert!(matches!(
+            e,
+            Error::ParseError(ParseError::UnexpectedComment { line: 3 })
+        ));
+
+        // duplicates are found across chunks
+        let line = ".pixiv.net\tTRUE\t/\tTRUE\t1784339332\ta\t1\n";
+        let mut builder = CookieJarBuilder::new().duplicate_policy(DuplicatePolicy::Error);
+        let tail = builder.parse_chunk(line).unwrap();
+        assert!(matches!(
+            builder.parse_chunk(&(tail + line)),
+            Err(Error::ParseError(ParseError::DuplicateName(name))) if name == "a"
+        ));
+
+        // a finished input starts over
+        let mut builder = CookieJarBuilder::new().duplicate_policy(DuplicatePolicy::Error);
+        let tail = builder.parse_chunk(line).unwrap();
+        builder.finish_chunks(&tail).unwrap();
+        let tail = builder.parse_chunk(line).unwrap();
+        builder.finish_chunks(&tail).unwrap();
+    }
+    #[test]
+    fn domain_lookup() {
+        let jar = parse(CONTENT).unwrap();
+        assert!(has_domain(&jar, "www.pixiv.net"));
+        assert!(has_domain(&jar, "pixiv.net"));
+        assert!(has_domain(&jar, "accounts.pixiv.net"));
+        assert!(!has_domain(&jar, "example.com"));
+        assert!(!has_domain(&jar, "notpixiv.net"));
+
+        let jar = parse("www.pixiv.net\tFALSE\t/\tTRUE\t0\ta\t1\n").unwrap();
+        assert!(has_domain(&jar, "WWW.pixiv.net"));
+        assert!(!has_domain(&jar, "pixiv.net"));
+        assert!(!has_domain(&jar, "sub.www.pixiv.net"));
+    }
+    #[test]
+    fn trailing_dot() {
+        let content = ".pixiv.net.\tTRUE\t/\tTRUE\t1784339332\tp_ab_id\t7\n";
+        let jar = parse(content).unwrap();
+        assert_eq!(
+            jar.get("p_ab_id").and_then(|c| c.domain()),
+            Some(".pixiv.net")
+        );
+        assert!(has_domain(&jar, "pixiv.net"));
+        let jar = CookieJarBuilder::new()
+            .strip_trailing_dot(false)
+            .parse(content)
+            .unwrap()
+            .finish();
+        assert_eq!(
+            jar.get("p_ab_id").and_then(|c| c.domain()),
+            Some(".pixiv.net.")
+        );
+        assert!(!has_domain(&jar, "pixiv.net"));
+    }
+    #[test]
+    fn lines_iter() {
+        let lines: Vec<&str> = CONTENT.lines().collect();
+        let jar = CookieJarBuilder::new()
+            .parse_lines_iter(lines)
+            .unwrap()
+            .finish();
+        assert_eq!(jar.iter().count(), parse(CONTENT).unwrap().iter().count());
+
+        let lines = vec!["", ".pixiv.net\tTRUE\t/\tTRUE\t1784339332"];
+        match CookieJarBuilder::new().parse_lines_iter(lines) {
+            Err(Error::ParseError(ParseError::TooFewFileds)) => {}
+            r => panic!("unexpected result {:?}", r.map(|b| b.finish())),
+        }
+    }
+    #[test]
+    fn expecting_count() {
+        assert_eq!(parse_expecting(CONTENT, 6).unwrap().iter().count(), 6);
+        match parse_expecting(CONTENT, 5) {
+            Err(Error::CountMismatch { expected, found }) => {
+                assert_eq!((expected, found), (5, 6))
+            }
+            r => panic!("unexpected result {:?}", r),
+        }
+    }
+    #[test]
+    fn csv_mode() {
+        let parse_csv = |s| {
+            CookieJarBuilder::new()
+                .csv_mode(true)
+                .parse(s)
+                .map(|b| b.finish())
+        };
+        let jar = parse_csv(
+            ".pixiv.net\tTRUE\t/\tTRUE\t1784339332\tquoted\t\"a\tb \"\"c\"\"\"\n\
+             \".pixiv.net\"\tTRUE\t\"/\"\tTRUE\t1784339332\tplain\t7\n",
+        )
+        .unwrap();
+        assert_eq!(jar.get("quoted").unwrap().value(), "a\tb \"c\"");
+        assert_eq!(jar.get("plain").unwrap().value(), "7");
+        assert_eq!(jar.get("plain").unwrap().domain(), Some(".pixiv.net"));
+        // unquoted values with a tab are still split
+        let jar = parse_csv(".pixiv.net\tTRUE\t/\tTRUE\t1784339332\tsplit\ta\tb\n").unwrap();
+        assert_eq!(jar.get("split").unwrap().value(), "a");
+        // without csv mode quotes are kept
+        let jar = parse(".pixiv.net\tTRUE\t/\tTRUE\t1784339332\tquoted\t\"a\tb\"\n").unwrap();
+        assert_eq!(jar.get("quoted").unwrap().value(), "\"a");
+        let jar = parse_csv(".pixiv.net\tTRUE\t/\tTRUE\t1784339332\topen\t\"a\n").unwrap();
+        assert_eq!(jar.get("open").unwrap().value(), "\"a");
+    }
+    #[test]
+    fn buffer_counted() {
+        let (builder, read) = CookieJarBuilder::new()
+            .parse_buffer_counted(CONTENT.as_bytes())
+            .unwrap();
+        assert_eq!(read, CONTENT.len());
+        assert_eq!(builder.finish().iter().count(), 6);
+    }
+    #[test]
+    fn session_expiry_spellings() {
+        let jar = parse(
+            ".pixiv.net\tTRUE\t/\tTRUE\tInfinity\tinfinity\t1\n\
+             .pixiv.net\tTRUE\t/\tTRUE\t\tempty\t2\n\
+             .pixiv.net\tTRUE\t/\tTRUE\t0\tzero\t3\n\
+             .pixiv.net\tTRUE\t/\tTRUE\t1784339332\tnumeric\t4\n",
+        )
+        .unwrap();
+        for name in ["infinity", "empty", "zero"] {
+            assert_eq!(jar.get(name).unwrap().expires_datetime(), None);
+        }
+        assert_eq!(
+            jar.get("numeric")
+                .and_then(|c| c.expires_datetime())
+                .map(|t| t.unix_timestamp()),
+            Some(1784339332)
+        );
+        assert!(parse(".pixiv.net\tTRUE\t/\tTRUE\t-Infinity\ta\t1\n").is_err());
+    }
+    #[test]
+    fn sniffing() {
+        let html = "<!DOCTYPE html>\n<html>\n<head><title>Log in</title></head>\n</html>\n";
+        let sniffed = |s| CookieJarBuilder::new().sniff(true).parse(s);
+        assert!(matches!(sniffed(html), Err(Error::NotNetscapeFormat)));
+        assert!(matches!(
+            sniffed("[{\"name\": \"p_ab_id\"}]"),
+            Err(Error::NotNetscapeFormat)
+        ));
+        assert_eq!(sniffed(CONTENT).unwrap().finish().iter().count(), 6);
+        // only the first cookie line is checked
+        let content = format!("{}broken\n", CONTENT);
+        assert!(matches!(
+            sniffed(&content),
+            Err(Error::ParseError(ParseError::TooFewFileds))
+        ));
+        assert!(matches!(
+            CookieJarBuilder::new().parse(html),
+            Err(Error::ParseError(ParseError::TooFewFileds))
+        ));
+    }
+    #[test]
+    fn expiry_order() {
+        let content = format!("{}.pixiv.net\tTRUE\t/\tTRUE\t0\tsession\t1\n", CONTENT);
+        let jar = parse(&content).unwrap();
+        let sorted = sorted_by_expiry(&jar);
+        assert_eq!(sorted.len(), 7);
+        assert_eq!(sorted[0].name(), "PHPSESSID");
+        assert_eq!(sorted[6].name(), "session");
+        let expiries: Vec<_> = sorted[..6]
+            .iter()
+            .map(|c| c.expires_datetime().unwrap().unix_timestamp())
+            .collect();
+        assert_eq!(
+            expiries,
+            [1626662932, 1689731332, 1689731332, 1784339332, 1784339332, 1784339332]
+        );
+    }
+    #[test]
+    fn strict_comments() {
+        let strict = |s| {
+            CookieJarBuilder::new()
+                .disallow_comments(true)
+                .parse(s)
+                .map(|b| b.finish())
+        };
+        let content = "# Netscape HTTP Cookie File\n\
+            #HttpOnly_.pixiv.net\tTRUE\t/\tTRUE\t1626662932\tPHPSESSID\tj6amv2\n\
+            # generated by a tool\n\
+            .pixiv.net\tTRUE\t/\tTRUE\t1784339332\tp_ab_id\t7\n";
+        match strict(content) {
+            Err(Error::ParseError(ParseError::UnexpectedComment { line })) => assert_eq!(line, 3),
+            r => panic!("unexpected result {:?}", r),
+        }
+        let content = content.replace("# generated by a tool\n", "");
+        assert_eq!(strict(&content).unwrap().iter().count(), 2);
+        assert_eq!(parse(CONTENT).unwrap().iter().count(), 6);
+    }
+    #[test]
+    fn lenient_fallback() {
+        let (builder, errors) = CookieJarBuilder::new().parse_or_lenient(CONTENT).unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(builder.finish().iter().count(), 6);
+
+        let corrupted = CONTENT.replace("\t1784339332\tp_ab_id\t", "\tsoon\tp_ab_id\t");
+        assert!(parse(&corrupted).is_err());
+        let (builder, errors) = CookieJarBuilder::new()
+            .parse_or_lenient(&corrupted)
+            .unwrap();
+        assert_eq!(errors, [ParseError::InvaildValue("soon".to_owned())]);
+        let jar = builder.finish();
+        assert_eq!(jar.iter().count(), 5);
+        assert!(jar.get("p_ab_id").is_none());
+
+        // every line is only parsed once
+        let calls = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = calls.clone();
+        let (builder, errors) = CookieJarBuilder::new()
+            .rename(move |n| {
+                counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                n.to_owned()
+            })
+            .parse_or_lenient(".pixiv.net\tTRUE\t/\tTRUE\t1784339332\ta\t1\nnot a cookie\n")
+            .unwrap();
+        assert_eq!(calls.load(std::sync::atomic::Ordering::Relaxed), 1);
+        assert_eq!(errors, [ParseError::TooFewFileds]);
+        assert_eq!(builder.finish().iter().count(), 1);
+
+        // errors handled by the builder are not returned
+        let (builder, errors) = CookieJarBuilder::new()
+            .on_error(ErrorMode::Collect)
+            .parse_or_lenient(&corrupted)
+            .unwrap();
+        assert!(errors.is_empty());
+        assert_eq!(builder.warnings().len(), 1);
+
+        // errors that are not about a line are not swallowed
+        let result = CookieJarBuilder::new()
+            .delimiter('\n')
+            .parse_or_lenient(&corrupted);
+        assert!(matches!(result, Err(Error::InvalidDelimiter('\n'))));
+    }
+    #[test]
+    fn http_only_filter() {
+        let content = format!(
+            "{}#HttpOnly_www.pixiv.net\tFALSE\t/\tTRUE\t0\tdevice_token\t1\n",
+            CONTENT
+        );
+        let jar = parse(&content).unwrap();
+        let mut names: Vec<_> = http_only_cookies(&jar).map(|c| c.name()).collect();
+        names.sort_unstable();
+        assert_eq!(names, ["PHPSESSID", "device_token"]);
+    }
+    #[test]
+    fn name_value_pairs() {
+        let pairs = pairs(&parse(CONTENT).unwrap());
+        assert_eq!(pairs.len(), 6);
+        assert!(pairs.contains(&("p_ab_id".to_owned(), "7".to_owned())));
+        assert!(pairs.contains(&("yuid_b".to_owned(), "FBdWQEY".to_owned())));
+    }
+    #[test]
+    fn line_length_limit() {
+        use std::io::BufReader;
+
+        let long = format!(
+            "{}.pixiv.net\tTRUE\t/\tTRUE\t1784339332\tlong\t{}\n",
+            CONTENT,
+            "x".repeat(100_000)
+        );
+        match CookieJarBuilder::new().max_line_len(256).parse(&long) {
+            Err(Error::ParseError(ParseError::LineTooLong { line, len })) => {
+                assert_eq!((line, len), (16, long.lines().last().unwrap().len()))
+            }
+            r => panic!("unexpected result {:?}", r.map(|b| b.finish())),
+        }
+        let jar = CookieJarBuilder::new()
+            .max_line_len(256)
+            .parse(CONTENT)
+            .unwrap()
+            .finish();
+        assert_eq!(jar.iter().count(), 6);
+        // lines read from a buffer are checked before they are kept whole
+        let result =
+            CookieJarBuilder::new()
+                .max_line_len(256)
+                .parse_buffer(BufReader::with_capacity(
+                    7,
+                    long.replace('\n', "\r\n").as_bytes(),
+                ));
+        assert!(matches!(
+            result,
+            Err(Error::ParseError(ParseError::LineTooLong { line: 16, len })) if len == long.lines().last().unwrap().len()
+        ));
+        let (builder, read) = CookieJarBuilder::new()
+            .max_line_len(256)
+            .parse_buffer_counted(CONTENT.as_bytes())
+            .unwrap();
+        assert_eq!((builder.finish().iter().count(), read), (6, CONTENT.len()));
+        let spaces = format!("{}\n{}", " ".repeat(300), CONTENT);
+        assert!(CookieJarBuilder::new()
+            .max_line_len(256)
+            .parse_buffer(spaces.as_bytes())
+            .is_err());
+
+        let mut names = Vec::new();
+        let result =
+            CookieJarBuilder::new()
+                .max_line_len(256)
+                .parse_streaming(long.as_bytes(), |c| {
+                    names.push(c.name().to_owned());
+                    ControlFlow::Continue(())
+                });
+        assert!(matches!(
+            result,
+            Err(Error::ParseError(ParseError::LineTooLong { line: 16, .. }))
+        ));
+        assert_eq!(names.len(), 6);
+    }
+    #[cfg(feature = "url")]
+    #[test]
+    fn url_cookies() {
+        use nescookie::cookies_for_url;
+
+        let content = format!(
+            "{}www.pixiv.net\tFALSE\t/novel\tFALSE\t0\tnovel\t1\n",
+            CONTENT
+        );
+        let jar = parse(&content).unwrap();
+        let names = |url| {
+            let mut names: Vec<_> = cookies_for_url(&jar, url)
+                .unwrap()
+                .into_iter()
+                .map(|c| c.name())
+                .collect();
+            names.sort_unstable();
+            names
+        };
+        assert_eq!(names("https://www.pixiv.net/ranking").len(), 6);
+        assert_eq!(names("https://www.pixiv.net/novel/show").len(), 7);
+        assert_eq!(
+            names("https://accounts.pixiv.net/login"),
+            ["PHPSESSID", "p_ab_d_id", "p_ab_id", "p_ab_id_2"]
+        );
+        assert_eq!(names("http://www.pixiv.net/novels"), Vec::<&str>::new());
+        assert_eq!(names("http://www.pixiv.net/novel"), ["novel"]);
+        assert!(names("https://example.com/").is_empty());
+        assert!(matches!(
+            cookies_for_url(&jar, "not a url"),
+            Err(Error::InvalidUrl(_))
+        ));
+    }
+    #[test]
+    fn count_only() {
+        assert_eq!(nescookie::count(CONTENT).unwrap(), 6);
+        assert!(matches!(
+            nescookie::count(&CONTENT.replace("\tFBdWQEY", "")),
+            Err(Error::ParseError(ParseError::TooFewFileds))
+        ));
+        assert_eq!(nescookie::count("# only a comment\n\n").unwrap(), 0);
+    }
+    #[test]
+    fn append_file() {
+        let path =
+            std::env::temp_dir().join(format!("nescookie-append-{}.txt", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let first = parse(CONTENT).unwrap();
+        nescookie::append(&first, &path).unwrap();
+        let more = parse(".pixiv.net\tTRUE\t/\tTRUE\t1784339332\tappended\t1\n").unwrap();
+        nescookie::append(&more, &path).unwrap();
+        // a file without a trailing newline is continued on a new line
+        CookieJarWriter::new()
+            .trailing_newline(false)
+            .append(
+                &parse(".pixiv.net\tTRUE\t/\tTRUE\t1784339332\tunterminated\t2\n").unwrap(),
+                &path,
+            )
+            .unwrap();
+        nescookie::append(
+            &parse(".pixiv.net\tTRUE\t/\tTRUE\t1784339332\tlast\t3\n").unwrap(),
+            &path,
+        )
+        .unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(content.matches("# Netscape HTTP Cookie File").count(), 1);
+        let missing = path.with_extension("d").join("x.txt");
+        assert!(matches!(
+            nescookie::append(&more, &missing),
+            Err(Error::Open { path, .. }) if path == missing
+        ));
+        let jar = parse(&content).unwrap();
+        assert_eq!(jar.iter().count(), 9);
+        assert_eq!(jar.get("appended").unwrap().value(), "1");
+        assert_eq!(jar.get("unterminated").unwrap().value(), "2");
+        assert_eq!(jar.get("last").unwrap().value(), "3");
+    }
+    #[test]
+    fn tolerated_errors() {
+        let tolerant =
+            || CookieJarBuilder::new().tolerate(|e| matches!(e, ParseError::InvaildValue(_)));
+        let corrupted = CONTENT.replace("\t1784339332\tp_ab_id\t", "\tsoon\tp_ab_id\t");
+        let builder = tolerant().parse(&corrupted).unwrap();
+        assert_eq!(
+            builder.warnings(),
+            [(12, ParseError::InvaildValue("soon".to_owned()))]
+        );
+        assert_eq!(builder.finish().iter().count(), 5);
+
+        let corrupted = corrupted.replace("\tFBdWQEY", "");
+        assert!(matches!(
+            tolerant().parse(&corrupted),
+            Err(Error::ParseError(ParseError::TooFewFileds))
+        ));
+    }
+    #[test]
+    fn extend_expiry() {
+        let mut jar = parse(CONTENT).unwrap();
+        let new = OffsetDateTime::from_unix_timestamp(4102444800);
+        assert!(set_expiry(&mut jar, "yuid_b", "www.pixiv.net", new));
+        let cookie = jar.get("yuid_b").unwrap();
+        assert_eq!(cookie.expires_datetime(), Some(new));
+        assert_eq!(cookie.value(), "FBdWQEY");
+        assert_eq!(cookie.domain(), Some("www.pixiv.net"));
+        assert_eq!(jar.iter().count(), 6);
+        assert!(!set_expiry(&mut jar, "yuid_b", ".pixiv.net", new));
+        assert!(!set_expiry(&mut jar, "missing", "www.pixiv.net", new));
+    }
+    #[test]
+    fn collapsed_tabs() {
+        let content = ".pixiv.net\tTRUE\t\t/\tTRUE\t\t1784339332\tp_ab_id\t\t7\n";
+        assert!(parse(content).is_err());
+        let jar = CookieJarBuilder::new()
+            .collapse_tabs(true)
+            .parse(content)
+            .unwrap()
+            .finish();
+        let cookie = jar.get("p_ab_id").unwrap();
+        assert_eq!(cookie.value(), "7");
+        assert_eq!(cookie.path(), Some("/"));
+        assert_eq!(cookie.secure(), Some(true));
+        assert_eq!(
+            cookie.expires_datetime().map(|t| t.unix_timestamp()),
+            Some(1784339332)
+        );
+
+        // other separators are not collapsed
+        let jar = CookieJarBuilder::new()
+            .delimiter(',')
+            .collapse_tabs(true)
+            .parse(".pixiv.net,TRUE,/,TRUE,1784339332,p_ab_id,\n")
+            .unwrap()
+            .finish();
+        assert_eq!(jar.get("p_ab_id").unwrap().value(), "");
+    }
+    #[test]
+    fn parse_metrics() {
+        let reported = std::sync::Arc::new(std::sync::Mutex::new(None));
+        let sink = reported.clone();
+        CookieJarBuilder::new()
+            .metrics(move |m| *sink.lock().unwrap() = Some(m))
+            .parse(CONTENT)
+            .unwrap()
+            .parse(".pixiv.net\tTRUE\t/\tTRUE\t1784339332\tlater\t1\n")
+            .unwrap();
+        let metrics: ParseMetrics = reported.lock().unwrap().unwrap();
+        assert_eq!(metrics.cookies_added, 6);
+        assert_eq!(metrics.lines_seen, 15);
+        assert!(metrics.bytes_read > 0);
+        assert_eq!(metrics.bytes_read, CONTENT.len());
+
+        // line breaks are only counted where there are some
+        for input in [
+            ".pixiv.net\tTRUE\t/\tTRUE\t1000\told\t1",
+            ".pixiv.net\tTRUE\t/\tTRUE\t1000\told\t1\r\n\n",
+        ] {
+            let sink = reported.clone();
+            CookieJarBuilder::new()
+                .metrics(move |m| *sink.lock().unwrap() = Some(m))
+                .parse(input)
+                .unwrap();
+            let metrics: ParseMetrics = reported.lock().unwrap().unwrap();
+            assert_eq!(metrics.bytes_read, input.len());
+        }
+    }
+    #[test]
+    fn metadata_directives() {
+        let content = format!("#@source=chrome\n#@ profile = Default \n{}", CONTENT);
+        let builder = CookieJarBuilder::new()
+            .extended_comments(true)
+            .parse(&content)
+            .unwrap();
+        assert_eq!(
+            builder.metadata().get("source").map(String::as_str),
+            Some("chrome")
+        );
+        assert_eq!(
+            builder.metadata().get("profile").map(String::as_str),
+            Some("Default")
+        );
+        assert_eq!(builder.metadata().len(), 2);
+        assert_eq!(builder.finish().iter().count(), 6);
+
+        let builder = CookieJarBuilder::new().parse(&content).unwrap();
+        assert!(builder.metadata().is_empty());
+    }
+    #[test]
+    fn per_domain_jars() {
+        let jars = split_by_domain(&parse(CONTENT).unwrap());
+        let counts: Vec<_> = jars
+            .iter()
+            .map(|(domain, jar)| (domain.as_str(), jar.iter().count()))
+            .collect();
+        assert_eq!(counts, [(".pixiv.net", 4), ("www.pixiv.net", 2)]);
+        assert!(jars["www.pixiv.net"].get("yuid_b").is_some());
+    }
+    #[test]
+    fn missing_expiry() {
+        let content = ".pixiv.net\tTRUE\t/\tTRUE\tp_ab_id\t7\n";
+        assert!(parse(content).is_err());
+        let jar = CookieJarBuilder::new()
+            .missing_expiry_is_session(true)
+            .parse(content)
+            .unwrap()
+            .finish();
+        let cookie = jar.get("p_ab_id").unwrap();
+        assert_eq!(cookie.value(), "7");
+        assert_eq!(cookie.expires_datetime(), None);
+        // complete lines are unaffected
+        let jar = CookieJarBuilder::new()
+            .missing_expiry_is_session(true)
+            .parse(CONTENT)
+            .unwrap()
+            .finish();
+        assert_eq!(jar.iter().count(), 6);
+        assert!(jar.iter().all(|c| c.expires_datetime().is_some()));
+    }
+    #[test]
+    fn extend_builder() {
+        let mut builder = CookieJarBuilder::new().parse(CONTENT).unwrap();
+        builder.extend(vec![
+            Cookie::build("manual", "1").domain(".pixiv.net").finish(),
+            Cookie::new("p_ab_id", "8"),
+        ]);
+        let jar = builder.finish();
+        assert_eq!(jar.iter().count(), 7);
+        assert_eq!(
+            jar.get("manual").and_then(|c| c.domain()),
+            Some(".pixiv.net")
+        );
+        assert_eq!(jar.get("p_ab_id").unwrap().value(), "8");
+    }
+    #[test]
+    fn duplicate_report() {
+        assert!(CookieJarBuilder::new()
+            .parse(CONTENT)
+            .unwrap()
+            .report_duplicates()
+            .is_empty());
+        let content = format!(
+            "{}www.pixiv.net\tFALSE\t/\tTRUE\t1689731332\tyuid_b\tagain\n\
+             .pixiv.net\tTRUE\t/\tTRUE\t1689731332\tyuid_b\tother_domain\n",
+            CONTENT
+        );
+        let builder = CookieJarBuilder::new().parse(&content).unwrap();
+        assert_eq!(
+            builder.report_duplicates(),
+            [("www.pixiv.net".to_owned(), "yuid_b".to_owned())]
+        );
+        assert_eq!(builder.finish().iter().count(), 6);
+    }
+    #[test]
+    fn max_age_for_sessions() {
+        let content = format!("{}.pixiv.net\tTRUE\t/\tTRUE\t0\tsession\t1\n", CONTENT);
+        let before = OffsetDateTime::now_utc().unix_timestamp();
+        let jar = CookieJarBuilder::new()
+            .default_max_age(std::time::Duration::from_secs(86400))
+            .parse(&content)
+            .unwrap()
+            .finish();
+        let after = OffsetDateTime::now_utc().unix_timestamp();
+        let exp = jar
+            .get("session")
+            .and_then(|c| c.expires_datetime())
+            .unwrap()
+            .unix_timestamp();
+        assert!((before + 86400..=after + 86400).contains(&exp));
+
+        let jar = CookieJarBuilder::new()
+            .default_max_age(Duration::MAX)
+            .parse(&content)
+            .unwrap()
+            .finish();
+        assert_eq!(
+            jar.get("session")
+                .and_then(|c| c.expires_datetime())
+                .map(|t| t.year()),
+            Some(9999)
+        );
+        assert_eq!(
+            jar.get("p_ab_id")
+                .and_then(|c| c.expires_datetime())
+                .map(|t| t.unix_timestamp()),
+            Some(1784339332)
+        );
+    }
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn zstd_file() {
+        let builder = CookieJarBuilder::new()
+            .open_zst("tests/cookies.txt.zst")
+            .unwrap();
+        assert_eq!(
+            builder.source_of("PHPSESSID"),
+            Some(Path::new("tests/cookies.txt.zst"))
+        );
+        assert_eq!(builder.finish().iter().count(), 6);
+        assert!(matches!(
+            CookieJarBuilder::new().open_zst("tests/cookies.txt"),
+            Err(Error::Archive(_))
+        ));
+    }
+    #[test]
+    fn whitespace_lines() {
+        let builder = CookieJarBuilder::new()
+            .flag_whitespace_lines(true)
+            .parse(&format!("{}\n\t\n    \n", CONTENT))
+            .unwrap();
+        assert_eq!(
+            builder.warnings(),
+            [
+                (11, ParseError::WhitespaceLine),
+                (18, ParseError::WhitespaceLine)
+            ]
+        );
+        assert_eq!(builder.finish().iter().count(), 6);
+        let builder = CookieJarBuilder::new().parse(CONTENT).unwrap();
+        assert!(builder.warnings().is_empty());
+
+        // only the field separator makes a line exempt
+        let builder = CookieJarBuilder::new()
+            .delimiter('|')
+            .flag_whitespace_lines(true)
+            .parse(" \t \n\t\n")
+            .unwrap();
+        assert_eq!(
+            builder.warnings(),
+            [
+                (1, ParseError::WhitespaceLine),
+                (2, ParseError::WhitespaceLine)
+            ]
+        );
+    }
+    #[test]
+    fn per_domain_limit() {
+        let content = ".pixiv.net\tTRUE\t/\tTRUE\t1784339332\ta\t1\n\
+            .pixiv.net\tTRUE\t/\tTRUE\t1784339332\tb\t2\n\
+            .pixiv.net\tTRUE\t/\tTRUE\t1784339332\tc\t3\n\
+            .pixiv.net\tTRUE\t/\tTRUE\t1784339332\ta\t4\n\
+            www.pixiv.net\tFALSE\t/\tTRUE\t1784339332\td\t5\n";
+        let jar = CookieJarBuilder::new()
+            .max_cookies_per_domain(2)
+            .parse(content)
+            .unwrap()
+            .finish();
+        assert_eq!(jar.iter().count(), 3);
+        assert_eq!(jar.get("a").unwrap().value(), "4");
+        assert!(jar.get("b").is_some());
+        assert!(jar.get("c").is_none());
+        assert!(jar.get("d").is_some());
+
+        // moving a cookie to another domain frees its slot
+        let moved = format!(
+            "{}www.pixiv.net\tFALSE\t/\tTRUE\t1784339332\ta\t6\n\
+             .pixiv.net\tTRUE\t/\tTRUE\t1784339332\te\t7\n",
+            content
+        );
+        let jar = CookieJarBuilder::new()
+            .max_cookies_per_domain(2)
+            .parse(&moved)
+            .unwrap()
+            .finish();
+        assert_eq!(jar.get("a").and_then(|c| c.domain()), Some("www.pixiv.net"));
+        assert!(jar.get("e").is_some());
+
+        let many: String = (0..20_000)
+            .map(|i| {
+                format!(
+                    "www{}.pixiv.net\tFALSE\t/\tTRUE\t1784339332\tc{}\t1\n",
+                    i % 100,
+                    i
+                )
+            })
+            .collect();
+        let jar = CookieJarBuilder::new()
+            .max_cookies_per_domain(50)
+            .parse(&many)
+            .unwrap()
+            .finish();
+        assert_eq!(jar.iter().count(), 5000);
+    }
+    #[test]
+    fn expiry_countdown() {
+        let jar = parse(CONTENT).unwrap();
+        let now = OffsetDateTime::from_unix_timestamp(1700000000);
+        assert_eq!(
+            seconds_until_expiry(jar.get("p_ab_id").unwrap(), now),
+            Some(84339332)
+        );
+        assert_eq!(
+            seconds_until_expiry(jar.get("PHPSESSID").unwrap(), now),
+            Some(-73337068)
+        );
+        assert_eq!(
+            seconds_until_expiry(&Cookie::new("session", "1"), now),
+            None
+        );
+    }
+    #[test]
+    fn write_unexpired() {
+        let content = format!("{}.pixiv.net\tTRUE\t/\tTRUE\t0\tsession\t1\n", CONTENT);
+        let jar = parse(&content).unwrap();
+        let s = CookieJarWriter::new()
+            .skip_expired(OffsetDateTime::from_unix_timestamp(1700000000))
+            .write_string(&jar);
+        assert!(!s.contains("PHPSESSID"));
+        let written = parse(&s).unwrap();
+        assert_eq!(written.iter().count(), 4);
+        assert!(written.get("session").is_some());
+        assert!(written.get("p_ab_id").is_some());
+    }
+    #[test]
+    fn bool_spellings() {
+        let line = |secure| format!(".pixiv.net\tTRUE\t/\t{}\t1784339332\tp_ab_id\t7\n", secure);
+        let lenient = |s: &str| {
+            CookieJarBuilder::new()
+                .bool_tokens(BoolStyle::Lenient)
+                .parse(s)
+                .map(|b| b.finish().get("p_ab_id").and_then(|c| c.secure()))
+        };
+        for (token, secure) in [
+            ("TRUE", true),
+            ("FALSE", false),
+            ("1", true),
+            ("0", false),
+            ("yes", true),
+            ("no", false),
+            ("True", true),
+            ("NO", false),
+        ] {
+            assert_eq!(lenient(&line(token)).unwrap(), Some(secure), "{}", token);
+        }
+        assert!(lenient(&line("maybe")).is_err());
+        assert!(parse(&line("TRUE")).is_ok());
+        for token in ["1", "0", "yes", "no"] {
+            assert!(parse(&line(token)).is_err());
+        }
+    }
+    #[test]
+    fn cookie_lines() {
+        struct Holder<'a> {
+            lines: CookieLines<'a>,
+        }
+        let content = CONTENT.replace("\tFBdWQEY", "");
+        let mut holder = Holder {
+            lines: CookieLines::new(&content),
+        };
+        let first = holder.lines.next().unwrap().unwrap();
+        assert_eq!(first.name(), "first_visit_datetime_pc");
+        assert_eq!(holder.lines.next().unwrap().unwrap().name(), "PHPSESSID");
+        assert_eq!(
+            holder.lines.by_ref().take(3).filter(Result::is_ok).count(),
+            3
+        );
+        assert_eq!(holder.lines.next(), Some(Err(ParseError::TooFewFileds)));
+        assert_eq!(holder.lines.next(), None);
+    }
+    #[cfg(feature = "miette")]
+    #[test]
+    fn diagnostics() {
+        use miette::Diagnostic;
+
+        let e = parse_results(&CONTENT.replace("\t1784339332\tp_ab_id\t", "\tsoon\tp_ab_id\t"))
+            .into_iter()
+            .find_map(Result::err)
+            .unwrap()
+            .1;
+        assert_eq!(e.code().unwrap().to_string(), "nescookie::invalid_value");
+        assert!(e.help().unwrap().to_string().contains("RFC 3339"));
+
+        let content = CONTENT
+            .replace("\t1784339332\tp_ab_id\t", "\tsoon\tp_ab_id\t")
+            .replace('\n', "\r\n");
+        let e = nescookie::SourceError::first("cookies.txt", &content).unwrap();
+        assert_eq!(e.line(), 12);
+        assert_eq!(e.error(), &ParseError::InvaildValue("soon".to_owned()));
+        let line = ".pixiv.net\tTRUE\t/\tTRUE\tsoon\tp_ab_id\t7";
+        let label = e.labels().unwrap().next().unwrap();
+        assert_eq!(label.offset(), content.find(line).unwrap());
+        assert_eq!(label.len(), line.len());
+        assert_eq!(label.label(), Some("line 12"));
+        assert!(e.source_code().is_some());
+        assert_eq!(e.code().unwrap().to_string(), "nescookie::invalid_value");
+    }
+    #[cfg(feature = "tar")]
+    #[test]
+    fn tar_entry() {
+        let builder = CookieJarBuilder::new()
+            .open_tar("tests/cookies.tar", "cookies.txt")
+            .unwrap();
+        assert_eq!(
+            builder.source_of("p_ab_id"),
+            Some(Path::new("tests/cookies.tar"))
+        );
+        assert_eq!(builder.finish().iter().count(), 6);
+        assert!(matches!(
+            CookieJarBuilder::new().open_tar("tests/cookies.tar", "missing.txt"),
+            Err(Error::Archive(_))
+        ));
+    }
+    #[test]
+    fn redacted_values() {
+        let jar = parse(CONTENT).unwrap();
+        let redacted = redact(&jar);
+        assert_eq!(redacted.iter().count(), 6);
+        for c in jar.iter() {
+            let r = redacted.get(c.name()).unwrap();
+            assert_eq!(r.value(), "REDACTED");
+            assert_eq!(
+                (r.domain(), r.path(), r.secure(), r.http_only(), r.expires()),
+                (c.domain(), c.path(), c.secure(), c.http_only(), c.expires())
+            );
+        }
+        assert!(!nescookie::to_string(&redacted).contains("j6amv2igf0cec4fdtld5rre5ud7ig3l2"));
+    }
+    #[test]
+    fn raw_round_trip() {
+        let write = |builder: &CookieJarBuilder| {
+            let mut buf = Vec::new();
+            builder.write(&mut buf).unwrap();
+            String::from_utf8(buf).unwrap()
+        };
+        let builder = CookieJarBuilder::new()
+            .keep_raw(true)
+            .parse(CONTENT)
+            .unwrap();
+        assert_eq!(write(&builder), CONTENT);
+
+        // line endings are normalized
+        let builder = CookieJarBuilder::new()
+            .keep_raw(true)
+            .parse(&CONTENT.replace('\n', "\r\n"))
+            .unwrap();
+        assert_eq!(write(&builder), CONTENT);
+        let builder = CookieJarBuilder::new()
+            .keep_raw(true)
+            .parse(CONTENT.trim_end())
+            .unwrap();
+        assert_eq!(write(&builder), CONTENT);
+
+        let mut builder = builder
+            .parse(".pixiv.net\tTRUE\t/\tTRUE\t1784339332\tp_ab_id\t8\n")
+            .unwrap();
+        builder.extend(vec![Cookie::build("manual", "1")
+            .domain(".pixiv.net")
+            .path("/")
+            .finish()]);
+        let written = write(&builder);
+        assert!(!written.contains("\tp_ab_id\t7\n"));
+        assert!(written.contains(".pixiv.net\tTRUE\t/\tTRUE\t1784339332\tp_ab_id\t8\n"));
+        assert!(written.ends_with(".pixiv.net\tTRUE\t/\tFALSE\t0\tmanual\t1\n"));
+        assert_eq!(parse(&written).unwrap().iter().count(), 7);
+
+        let builder = CookieJarBuilder::new().parse(CONTENT).unwrap();
+        assert_eq!(
+            write(&builder),
+            nescookie::to_string(&parse(CONTENT).unwrap())
+        );
+
+        // cookies changed by options are not written as they were read
+        let content = ".pixiv.net\tTRUE\t/\tTRUE\t1784339332\ta\t1\n\
+                       .pixiv.net\tTRUE\t/\tMAYBE\t1784339332\tbroken\t2\n\
+                       .pixiv.net\tTRUE\t/api\tTRUE\t1784339332\tb\t3\n";
+        let builder = CookieJarBuilder::new()
+            .keep_raw(true)
+            .force_path("/api")
+            .rename(|n| format!("p1_{}", n))
+            .on_error(ErrorMode::Skip)
+            .parse(content)
+            .unwrap();
+        let written = write(&builder);
+        assert!(written.contains(".pixiv.net\tTRUE\t/api\tTRUE\t1784339332\tp1_a\t1\n"));
+        // lines skipped because of errors are kept
+        assert!(written.contains("\tMAYBE\t1784339332\tbroken\t2\n"));
+        let reread = CookieJarBuilder::new()
+            .on_error(ErrorMode::Skip)
+            .parse(&written)
+            .unwrap()
+            .finish();
+        let jar = builder.finish();
+        assert_eq!(reread.iter().count(), 2);
+        for c in jar.iter() {
+            assert_eq!(reread.get(c.name()), Some(c));
+        }
+    }
+    #[test]
+    fn disabled_records() {
+        let content = format!(
+            "{}#.pixiv.net\tTRUE\t/\tTRUE\t1784339332\tdisabled\t1\n\
+             # .pixiv.net\tTRUE\t/\tMAYBE\t1784339332\tbroken\t2\n",
+            CONTENT
+        );
+        let jar = parse(&content).unwrap();
+        assert_eq!(jar.iter().count(), 6);
+        let jar = CookieJarBuilder::new()
+            .parse_disabled(true)
+            .parse(&content)
+            .unwrap()
+            .finish();
+        assert_eq!(jar.iter().count(), 7);
+        let cookie = jar.get("disabled").unwrap();
+        assert_eq!(cookie.domain(), Some(".pixiv.net"));
+        assert_eq!(cookie.http_only(), None);
+        assert!(jar.get("broken").is_none());
+        assert!(jar.get("PHPSESSID").unwrap().http_only().unwrap());
+
+        // a deeply nested prefix is kept as a comment instead of recursing
+        let nested = format!(
+            "{}.pixiv.net\tTRUE\t/\tTRUE\t1784339332\tnested\t1\n##HttpOnly_.pixiv.net\tTRUE\t/\tTRUE\t1784339332\thttp_only\t1\n",
+            "#".repeat(200_000)
+        );
+        let jar = CookieJarBuilder::new()
+            .parse_disabled(true)
+            .parse(&nested)
+            .unwrap()
+            .finish();
+        assert!(jar.get("nested").is_none());
+        assert_eq!(jar.get("http_only").and_then(|c| c.http_only()), Some(true));
+    }
+    #[test]
+    fn earliest_expiry() {
+        let jar = parse(CONTENT).unwrap();
+        assert_eq!(
+            min_expiry(&jar).map(|t| t.unix_timestamp()),
+            Some(1626662932)
+        );
+        let jar = parse(".pixiv.net\tTRUE\t/\tTRUE\t0\tsession\t1\n").unwrap();
+        assert_eq!(min_expiry(&jar), None);
+    }
+    #[test]
+    fn concatenated_files() {
+        let a = "# Netscape HTTP Cookie File\n\
+            .pixiv.net\tTRUE\t/\tTRUE\t1784339332\tp_ab_id\t7\n\
+            .pixiv.net\tTRUE\t/\tTRUE\t1784339332\tfrom_a\t1\n";
+        let b = "# Netscape HTTP Cookie File\n\
+            .example.com\tTRUE\t/\tFALSE\t0\tfrom_b\t2\n\
+            .pixiv.net\tTRUE\t/\tTRUE\t1784339332\tp_ab_id\t8\n";
+        let combined = format!("{}{}", a, b);
+        let jar = parse(&combined).unwrap();
+        assert_eq!(jar.iter().count(), 3);
+        assert_eq!(jar.get("from_a").unwrap().value(), "1");
+        assert_eq!(jar.get("from_b").unwrap().domain(), Some(".example.com"));
+        assert_eq!(jar.get("p_ab_id").unwrap().value(), "8");
+        // duplicates across files are not duplicates within a source
+        let jar = CookieJarBuilder::new()
+            .duplicate_policy(DuplicatePolicy::Error)
+            .parse(&combined)
+            .unwrap()
+            .finish();
+        assert_eq!(jar.iter().count(), 3);
+        let jar = CookieJarBuilder::new()
+            .duplicate_policy(DuplicatePolicy::KeepFirst)
+            .parse(&combined)
+            .unwrap()
+            .finish();
+        assert_eq!(jar.get("p_ab_id").unwrap().value(), "8");
+        assert!(CookieJarBuilder::new()
+            .duplicate_policy(DuplicatePolicy::Error)
+            .parse(&format!(
+                "{}{}",
+                a,
+                b.replace("# Netscape HTTP Cookie File\n", "")
+            ))
+            .is_err());
+    }
+    #[test]
+    fn domain_flag() {
+        let domain = |domain, flag, from_flag| {
+            let content = format!("{}\t{}\t/\tTRUE\t1784339332\tp_ab_id\t7\n", domain, flag);
+            CookieJarBuilder::new()
+                .domain_from_flag(from_flag)
+                .parse(&content)
+                .unwrap()
+                .finish()
+                .get("p_ab_id")
+                .and_then(|c| c.domain().map(str::to_owned))
+                .unwrap()
+        };
+        assert_eq!(domain(".pixiv.net", "TRUE", true), ".pixiv.net");
+        assert_eq!(domain(".pixiv.net", "FALSE", true), "pixiv.net");
+        assert_eq!(domain("pixiv.net", "TRUE", true), ".pixiv.net");
+        assert_eq!(domain("pixiv.net", "FALSE", true), "pixiv.net");
+        assert_eq!(domain(".pixiv.net", "FALSE", false), ".pixiv.net");
+        assert_eq!(domain("pixiv.net", "TRUE", false), "pixiv.net");
+        assert!(CookieJarBuilder::new()
+            .domain_from_flag(true)
+            .parse("pixiv.net\tMAYBE\t/\tTRUE\t1784339332\tp_ab_id\t7\n")
+            .is_err());
+    }
+    #[test]
+    fn name_map() {
+        let map = nescookie::parse_map(CONTENT).unwrap();
+        assert_eq!(map.len(), 6);
+        assert_eq!(map["p_ab_d_id"].value(), "620724492");
+        assert_eq!(map["yuid_b"].domain(), Some("www.pixiv.net"));
+        assert!(map["PHPSESSID"].http_only().unwrap());
+        assert!(!map.contains_key("missing"));
+        let map = nescookie::parse_map(&format!(
+            "{}.pixiv.net\tTRUE\t/\tTRUE\t1784339332\tp_ab_id\tlast\n",
+            CONTENT
+        ))
+        .unwrap();
+        assert_eq!(map["p_ab_id"].value(), "last");
+    }
+    #[test]
+    fn curl_dialect() {
+        let content = "#HttpOnly_.pixiv.net\tTRUE\t/\tTRUE\t1784339332\tPHPSESSID\tabc\n";
+        let mut jar = parse(content).unwrap();
+        jar.add(Cookie::new("no_domain", "1"));
+        let s = CookieJarWriter::new()
+            .dialect(Dialect::Curl)
+            .write_string(&jar);
+        assert_eq!(
+            s,
+            "# Netscape HTTP Cookie File\n\
+             # https://curl.se/docs/http-cookies.html\n\
+             # This file was generated by libcurl! Edit at your own risk.\n\
+             \n\
+             #HttpOnly_.pixiv.net\tTRUE\t/\tTRUE\t1784339332\tPHPSESSID\tabc\n"
+        );
+        assert!(CookieJarWriter::new()
+            .write_string(&jar)
+            .contains("no_domain"));
+    }
+    #[test]
+    fn error_modes() {
+        let corrupted = CONTENT.replace("\t1784339332\tp_ab_id\t", "\tsoon\tp_ab_id\t");
+        let with_mode = |mode| CookieJarBuilder::new().on_error(mode).parse(&corrupted);
+
+        assert!(matches!(
+            with_mode(ErrorMode::FailFast),
+            Err(Error::ParseError(ParseError::InvaildValue(v))) if v == "soon"
+        ));
+
+        let builder = with_mode(ErrorMode::Skip).unwrap();
+        assert!(builder.warnings().is_empty());
+        assert_eq!(builder.finish().iter().count(), 5);
+
+        let builder = with_mode(ErrorMode::Collect).unwrap();
+        assert_eq!(
+            builder.warnings(),
+            [(12, ParseError::InvaildValue("soon".to_owned()))]
+        );
+        assert_eq!(builder.finish().iter().count(), 5);
+    }
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn mmap_file() {
+        let jar = CookieJarBuilder::new()
+            .open_mmap("tests/cookies.txt")
+            .unwrap()
+            .finish();
+        assert_eq!(jar.iter().count(), 6);
+        assert_eq!(jar.get("p_ab_id").map(|c| c.value()), Some("7"));
+    }
+    #[test]
+    fn expiring_cookies() {
+        let at = |t| OffsetDateTime::from_unix_timestamp(t);
+        let mut jar = parse("").unwrap();
+        for (name, exp) in [
+            ("past", Some(900)),
+            ("now", Some(1000)),
+            ("soon", Some(1500)),
+            ("edge", Some(2000)),
+            ("later", Some(2001)),
+            ("session", None),
+        ] {
+            let mut cookie = Cookie::new(name, "1");
+            if let Some(exp) = exp {
+                cookie.set_expires(at(exp));
+            }
+            jar.add(cookie);
+        }
+        let mut names: Vec<_> = expiring_within(&jar, Duration::from_secs(1000), at(1000))
+            .into_iter()
+            .map(|c| c.name())
+            .collect();
+        names.sort_unstable();
+        assert_eq!(names, ["edge", "soon"]);
+        assert!(expiring_within(&jar, Duration::ZERO, at(1000)).is_empty());
+        let mut names: Vec<_> = expiring_within(&jar, Duration::MAX, at(1000))
+            .into_iter()
+            .map(|c| c.name())
+            .collect();
+        names.sort_unstable();
+        assert_eq!(names, ["edge", "later", "soon"]);
+    }
+    #[test]
+    fn value_rotation() {
+        let mut jar = parse(CONTENT).unwrap();
+        let before = to_record(jar.get("PHPSESSID").unwrap());
+        assert!(set_value(&mut jar, "PHPSESSID", ".PIXIV.net", "rotated"));
+        let after = to_record(jar.get("PHPSESSID").unwrap());
+        assert_eq!(
+            after,
+            Record {
+                value: "rotated".to_owned(),
+                ..before
+            }
+        );
+        assert!(!set_value(&mut jar, "PHPSESSID", "www.pixiv.net", "other"));
+        assert!(!set_value(&mut jar, "missing", ".pixiv.net", "other"));
+        assert_eq!(jar.get("PHPSESSID").unwrap().value(), "rotated");
+    }
+    #[test]
+    fn streaming() {
+        let mut names = Vec::new();
+        parse_streaming(CONTENT.as_bytes(), |c| {
+            names.push(c.name().to_owned());
+            if names.len() == 2 {
+                ControlFlow::Break(())
+            } else {
+                ControlFlow::Continue(())
+            }
+        })
+        .unwrap();
+        assert_eq!(names, ["first_visit_datetime_pc", "PHPSESSID"]);
+
+        let result = parse_streaming(".pixiv.net\tTRUE\n".as_bytes(), |_| {
+            ControlFlow::Continue(())
+        });
+        assert!(matches!(
+            result,
+            Err(Error::ParseError(ParseError::TooFewFileds))
+        ));
+
+        // the options of the builder apply as they do when parsing
+        let content = "#@source=export\n\
+                       .pixiv.net\tTRUE\t/\tTRUE\t1000\told\t1\n\
+                       .pixiv.net\tTRUE\n\
+                       .pixiv.net\tTRUE\t/\tTRUE\t3000\tnew\t2\n\
+                       .pixiv.net\tTRUE\t/\tTRUE\t2500\tnew\t3\n\
+                       .pixiv.net\tTRUE\t/\tTRUE\t4000\tlast\t4\n";
+        let builder = || {
+            CookieJarBuilder::new()
+                .skip_expired(OffsetDateTime::from_unix_timestamp(2000))
+                .on_error(ErrorMode::Collect)
+                .rename(|n| format!("x_{}", n))
+                .prefer_latest_expiry(true)
+                .extended_comments(true)
+                .max_cookies_per_domain(1)
+        };
+        let mut streaming = builder();
+        let mut streamed = Vec::new();
+        streaming
+            .parse_streaming(content.as_bytes(), |c| {
+                streamed.push((c.name().to_owned(), c.value().to_owned()));
+                ControlFlow::Continue(())
+            })
+            .unwrap();
+        assert_eq!(streamed, [("x_new".to_owned(), "2".to_owned())]);
+        let parsed = builder().parse(content).unwrap();
+        assert_eq!(streaming.warnings(), parsed.warnings());
+        assert_eq!(streaming.metadata(), parsed.metadata());
+        let jar = parsed.finish();
+        assert_eq!(jar.iter().count(), 1);
+        assert_eq!(jar.get("x_new").unwrap().value(), "2");
+    }
+    #[test]
+    fn empty_names() {
+        let empty = ".pixiv.net\tTRUE\t/\tTRUE\t1784339332\t\t7\n";
+        let with_setting = |allow, s| CookieJarBuilder::new().allow_empty_name(allow).parse(s);
+
+        assert!(matches!(
+            with_setting(false, empty),
+            Err(Error::ParseError(ParseError::InvalidName { line: 1, name })) if name.is_empty()
+        ));
+        assert!(matches!(
+            parse(empty),
+            Err(Error::ParseError(ParseError::InvalidName { .. }))
+        ));
+        let jar = with_setting(true, empty).unwrap().finish();
+        assert_eq!(jar.get("").map(|c| c.value()), Some("7"));
+
+        for allow in [false, true] {
+            let jar = with_setting(allow, CONTENT).unwrap().finish();
+            assert_eq!(jar.iter().count(), 6);
+        }
+    }
+    #[test]
+    fn cookie_header_pairs() {
+        let mut jar = parse("").unwrap();
+        add_cookie_header(&mut jar, "a=b; c=d=e;invalid", "www.pixiv.net", "/api");
+        assert_eq!(jar.iter().count(), 2);
+        assert_eq!(jar.get("a").map(|c| c.value()), Some("b"));
+        let c = jar.get("c").unwrap();
+        assert_eq!(c.value(), "d=e");
+        assert_eq!(c.domain(), Some("www.pixiv.net"));
+        assert_eq!(c.path(), Some("/api"));
+    }
+    #[cfg(feature = "percent-encoding")]
+    #[test]
+    fn decoded_values() {
+        let value = |decode| {
+            let jar = CookieJarBuilder::new()
+                .decode_values(decode)
+                .parse(CONTENT)
+                .unwrap()
+                .finish();
+            jar.get("first_visit_datetime_pc")
+                .unwrap()
+                .value()
+                .to_owned()
+        };
+        assert_eq!(value(true), "2021-07-19+10:48:50");
+        assert_eq!(value(false), "2021-07-19+10%3A48%3A50");
+    }
+    #[test]
+    fn table_rows() {
+        let rows = rows(&parse(CONTENT).unwrap());
+        let lines: Vec<_> = rows.iter().map(|r| r.to_string()).collect();
+        let mut expected: Vec<_> = CONTENT
+            .lines()
+            .filter(|l| {
+                !l.trim().is_empty() && (!l.starts_with('#') || l.starts_with("#HttpOnly_"))
+            })
+            .collect();
+        expected.sort_by_key(|l| {
+            let fields: Vec<_> = l.trim_start_matches("#HttpOnly_").split('\t').collect();
+            (fields[0], fields[2], fields[5])
+        });
+        assert_eq!(lines, expected);
+        assert!(rows[0].http_only);
+        assert_eq!(rows[0].domain, ".pixiv.net");
+    }
+    #[test]
+    fn first_errors() {
+        assert_eq!(first_error(CONTENT), None);
+        let dirty = CONTENT
+            .replace("\t1784339332\tp_ab_id\t", "\tsoon\tp_ab_id\t")
+            .replace("\tp_ab_d_id\t620724492", "");
+        assert_eq!(
+            first_error(&dirty),
+            Some((12, ParseError::InvaildValue("soon".to_owned())))
+        );
+    }
+    #[test]
+    fn parse_into_borrowed_jar() {
+        let mut jar = parse(CONTENT).unwrap();
+        let content = ".pixiv.net\tTRUE\t/\tTRUE\t1784339332\tp_ab_id\t8\n\
+                       .pixiv.net\tTRUE\t/\tTRUE\t1784339332\textra\t1\n";
+        assert_eq!(parse_into(&mut jar, content).unwrap(), 2);
+        assert_eq!(jar.iter().count(), 7);
+        assert_eq!(jar.get("p_ab_id").map(|c| c.value()), Some("8"));
+
+        assert!(parse_into(&mut jar, "not a cookie\n").is_err());
+        assert_eq!(jar.iter().count(), 7);
+    }
+    #[test]
+    fn colliding_names() {
+        let content = format!(
+            "{}www.pixiv.net\tFALSE\t/\tTRUE\t0\tp_ab_id\t1\n\
+             pixiv.net\tFALSE\t/\tTRUE\t0\tp_ab_id\t2\n\
+             pixiv.net\tFALSE\t/api\tTRUE\t0\tyuid_b\t3\n",
+            CONTENT
+        );
+        let records: Vec<_> = parse_results(&content)
+            .iter()
+            .flatten()
+            .map(to_record)
+            .collect();
+        assert_eq!(
+            name_collisions(&records),
+            [
+                (
+                    "p_ab_id".to_owned(),
+                    vec![
+                        ".pixiv.net".to_owned(),
+                        "pixiv.net".to_owned(),
+                        "www.pixiv.net".to_owned()
+                    ]
+                ),
+                (
+                    "yuid_b".to_owned(),
+                    vec!["pixiv.net".to_owned(), "www.pixiv.net".to_owned()]
+                ),
+            ]
+        );
+        let records: Vec<_> = rows(&parse(CONTENT).unwrap());
+        assert!(name_collisions(&records).is_empty());
+    }
+    #[test]
+    fn custom_delimiters() {
+        let content = CONTENT.replace('\t', "\0");
+        let jar = CookieJarBuilder::new()
+            .delimiter('\0')
+            .parse(&content)
+            .unwrap()
+            .finish();
+        assert_eq!(jar.iter().count(), 6);
+        assert_eq!(jar.get("p_ab_d_id").map(|c| c.value()), Some("620724492"));
+        assert_eq!(jar.get("PHPSESSID").and_then(|c| c.http_only()), Some(true));
+        assert!(CookieJarBuilder::new().parse(&content).is_err());
+
+        assert!(matches!(
+            CookieJarBuilder::new().delimiter('\n').parse(CONTENT),
+            Err(Error::InvalidDelimiter('\n'))
+        ));
+    }
+    #[test]
+    fn fingerprints() {
+        let jar = parse(CONTENT).unwrap();
+        let mut cookies: Vec<_> = jar.iter().cloned().collect();
+        cookies.reverse();
+        let mut reversed = parse("").unwrap();
+        for c in cookies {
+            reversed.add(c);
+        }
+        assert_eq!(fingerprint(&jar), fingerprint(&reversed));
+        assert_ne!(fingerprint(&jar), fingerprint(&redact(&jar)));
+
+        // the hash is specified, so it is fixed across runs and platforms
+        assert_eq!(fingerprint(&parse("").unwrap()), 0xcbf29ce484222325);
+        let mut jar = parse("").unwrap();
+        jar.add(
+            Cookie::build("p_ab_id", "7")
+                .domain(".pixiv.net")
+                .path("/")
+                .secure(true)
+                .expires(OffsetDateTime::from_unix_timestamp(1784339332))
+                .finish(),
+        );
+        jar.add(
+            Cookie::build("PHPSESSID", "abc")
+                .domain(".pixiv.net")
+                .path("/")
+                .secure(true)
+                .http_only(true)
+                .expires(OffsetDateTime::from_unix_timestamp(1626662932))
+                .finish(),
+        );
+        assert_eq!(fingerprint(&jar), 0x49f8663977a7114a);
+    }
+    #[test]
+    fn cookie_dir() {
+        let dir = std::env::temp_dir().join(format!("nescookie-dir-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("nested.txt")).unwrap();
+        std::fs::write(dir.join("a.txt"), CONTENT).unwrap();
+        std::fs::write(
+            dir.join("b.txt"),
+            ".pixiv.net\tTRUE\t/\tTRUE\t1784339332\tp_ab_id\t8\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("notes.md"), "not a cookie\n").unwrap();
+        let builder = CookieJarBuilder::new().open_dir(&dir).unwrap();
+        assert_eq!(
+            builder.source_of("p_ab_id"),
+            Some(dir.join("b.txt").as_path())
+        );
+        let jar = builder.finish();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(jar.iter().count(), 6);
+        assert_eq!(jar.get("p_ab_id").map(|c| c.value()), Some("8"));
+
+        // a missing directory is named in the error
+        assert!(matches!(
+            CookieJarBuilder::new().open_dir(&dir),
+            Err(Error::Open { path, .. }) if path == dir
+        ));
+    }
+    #[test]
+    fn lowercase_booleans() {
+        let content = "# Netscape HTTP Cookie File\n\
+                       .pixiv.net\ttrue\t/\ttrue\t1784339332\tp_ab_id\t7\n\
+                       www.pixiv.net\tfalse\t/\tfalse\t1689731332\tyuid_b\tFBdWQEY\n";
+        let jar = CookieJarBuilder::new()
+            .bool_tokens(BoolStyle::Lenient)
+            .parse(content)
+            .unwrap()
+            .finish();
+        let writer = CookieJarWriter::new();
+        assert_eq!(
+            writer.clone().bool_case(Case::Lower).write_string(&jar),
+            content
+        );
+        assert_eq!(
+            writer.write_string(&jar),
+            content.replace("true", "TRUE").replace("false", "FALSE")
+        );
+    }
+    #[test]
+    fn same_site() {
+        let jar = CookieJarBuilder::new()
+            .default_same_site(SameSite::Lax)
+            .parse(CONTENT)
+            .unwrap()
+            .finish();
+        assert_eq!(jar.iter().count(), 6);
+        assert!(jar.iter().all(|c| c.same_site() == Some(SameSite::Lax)));
+        assert!(parse(CONTENT)
+            .unwrap()
+            .iter()
+            .all(|c| c.same_site().is_none()));
+    }
+    #[test]
+    fn header_only() {
+        for content in [
+            "# Netscape HTTP Cookie File\n",
+            "\u{feff}# Netscape HTTP Cookie File\n",
+        ] {
+            assert_eq!(parse(content).unwrap().iter().count(), 0);
+            assert_eq!(validate(content), Ok(()));
+            assert_eq!(nescookie::count(content).unwrap(), 0);
+            assert_eq!(CookieLines::new(content).count(), 0);
+        }
+        let with_bom = format!("\u{feff}{}", CONTENT);
+        assert_eq!(parse(&with_bom).unwrap().iter().count(), 6);
+        assert_eq!(nescookie::count(&with_bom).unwrap(), 6);
+        assert!(CookieLines::new(&with_bom).all(|c| c.is_ok()));
+        let jar = parse("\u{feff}.pixiv.net\tTRUE\t/\tTRUE\t1784339332\tp_ab_id\t7\n").unwrap();
+        assert_eq!(
+            jar.get("p_ab_id").and_then(|c| c.domain()),
+            Some(".pixiv.net")
+        );
+    }
+    #[cfg(feature = "rusqlite")]
+    #[test]
+    fn firefox_sqlite() {
+        use rusqlite::Connection;
+
+        let jar = parse(CONTENT).unwrap();
+        let conn = Connection::open_in_memory().unwrap();
+        assert_eq!(nescookie::to_sqlite(&jar, &conn).unwrap(), 6);
+        let row = conn
+            .query_row(
+                "SELECT host, path, expiry, isSecure, isHttpOnly, value FROM moz_cookies WHERE name = ?1",
+                ["PHPSESSID"],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, i64>(2)?,
+                        row.get::<_, bool>(3)?,
+                        row.get::<_, bool>(4)?,
+                        row.get::<_, String>(5)?,
+                    ))
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            row,
+            (
+                ".pixiv.net".to_owned(),
+                "/".to_owned(),
+                1626662932,
+                true,
+                true,
+                "j6amv2igf0cec4fdtld5rre5ud7ig3l2".to_owned()
+            )
+        );
+        // writing again replaces the rows instead of duplicating them
+        nescookie::to_sqlite(&jar, &conn).unwrap();
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM moz_cookies", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 6);
+    }
+    #[test]
+    fn path_prefixes() {
+        let content = format!(
+            "{}.pixiv.net\tTRUE\t/api\tTRUE\t1784339332\tapi_token\t1\n\
+             .pixiv.net\tTRUE\t/apis\tTRUE\t1784339332\tapis_token\t2\n",
+            CONTENT
+        );
+        let with_prefix = |prefix| {
+            CookieJarBuilder::new()
+                .path_prefix(prefix)
+                .parse(&content)
+                .unwrap()
+                .finish()
+        };
+        assert_eq!(with_prefix("/").iter().count(), 8);
+        let jar = with_prefix("/api/");
+        assert_eq!(jar.iter().count(), 0);
+        let jar = with_prefix("/api");
+        let mut names: Vec<_> = jar.iter().map(|c| c.name()).collect();
+        names.sort_unstable();
+        assert_eq!(names, ["api_token", "apis_token"]);
+        assert_eq!(with_prefix("/x").iter().count(), 0);
+    }
+    #[cfg(feature = "futures")]
+    #[test]
+    fn byte_stream() {
+        use futures::{executor::block_on, stream};
+        use std::io;
+
+        let line = ".pixiv.net\tTRUE\t/\tTRUE\t1784339332\tnickname\tピクシブ\n".as_bytes();
+        // split the cookie and a multi-byte character across chunks
+        let split = line.len() - 5;
+        let chunks = stream::iter([&line[..split], &line[split..]].map(Ok::<_, io::Error>));
+        let jar = block_on(nescookie::parse_stream(chunks)).unwrap();
+        assert_eq!(jar.get("nickname").map(|c| c.value()), Some("ピクシブ"));
+
+        let chunks = stream::iter(CONTENT.as_bytes().chunks(7).map(Ok::<_, io::Error>));
+        assert_eq!(
+            block_on(nescookie::parse_stream(chunks))
+                .unwrap()
+                .iter()
+                .count(),
+            6
+        );
+
+        // errors of the stream are returned
+        let chunks = stream::iter([Ok(line), Err(io::Error::other("reset"))]);
+        assert!(matches!(
+            block_on(nescookie::parse_stream(chunks)),
+            Err(Error::IoError(e)) if e.to_string() == "reset"
+        ));
+
+        // the final line keeps counting from the earlier chunks
+        let chunks = stream::iter([Ok::<_, io::Error>(line), Ok(&line[..line.len() - 1])]);
+        let result = block_on(
+            CookieJarBuilder::new()
+                .duplicate_policy(DuplicatePolicy::Error)
+                .parse_stream(chunks),
+        );
+        assert!(matches!(
+            result,
+            Err(Error::ParseError(ParseError::DuplicateName(_)))
+        ));
+    }
+    #[test]
+    fn preserved_comments() {
+        let content = "# Netscape HTTP Cookie File\n\
+                       # session\n\
+                       #HttpOnly_.pixiv.net\tTRUE\t/\tTRUE\t1626662932\tPHPSESSID\tabc\n\
+                       \n\
+                       # tracking\n\
+                       www.pixiv.net\tFALSE\t/\tTRUE\t1689731332\tyuid_b\tFBdWQEY\n\
+                       # ab tests\n\
+                       .pixiv.net\tTRUE\t/\tTRUE\t1784339332\tp_ab_id\t7\n";
+        let mut builder = CookieJarBuilder::new()
+            .preserve_comments(true)
+            .parse(content)
+            .unwrap();
+        let mut buf = Vec::new();
+        builder.write(&mut buf).unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), content);
+
+        builder.extend([
+            Cookie::build("yuid_b", "changed")
+                .domain("www.pixiv.net")
+                .path("/")
+                .secure(true)
+                .expires(OffsetDateTime::from_unix_timestamp(1689731332))
+                .finish(),
+            Cookie::build("added", "1").domain(".pixiv.net").finish(),
+        ]);
+        let mut buf = Vec::new();
+        builder.write(&mut buf).unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            content.replace("FBdWQEY", "changed") + ".pixiv.net\tTRUE\t/\tFALSE\t0\tadded\t1\n"
+        );
+    }
+    #[test]
+    fn missing_paths() {
+        let repairing = || CookieJarBuilder::new().repair_missing_path(true);
+
+        let missing = ".pixiv.net\tTRUE\tFALSE\t1784339332\tp_ab_id\t7\n";
+        let jar = repairing().parse(missing).unwrap().finish();
+        let c = jar.get("p_ab_id").unwrap();
+        assert_eq!(c.path(), Some("/"));
+        assert_eq!(c.secure(), Some(false));
+        assert_eq!(c.value(), "7");
+        assert!(parse(missing).is_err());
+
+        // a missing value or expiration leaves a path in the third field
+        let missing_value = ".pixiv.net\tTRUE\t/\tTRUE\t1784339332\tp_ab_id\n";
+        assert!(repairing().parse(missing_value).is_err());
+        let jar = repairing()
+            .allow_missing_value(true)
+            .parse(missing_value)
+            .unwrap()
+            .finish();
+        assert_eq!(jar.get("p_ab_id").map(|c| c.value()), Some(""));
+        let missing_expiry = ".pixiv.net\tTRUE\t/api\tTRUE\tp_ab_id\t7\n";
+        let jar = repairing()
+            .missing_expiry_is_session(true)
+            .parse(missing_expiry)
+            .unwrap()
+            .finish();
+        assert_eq!(jar.get("p_ab_id").and_then(|c| c.path()), Some("/api"));
+
+        assert_eq!(
+            repairing().parse(CONTENT).unwrap().finish().iter().count(),
+            6
+        );
+    }
+    #[test]
+    fn secure_partition() {
+        let mut jar = parse(CONTENT).unwrap();
+        let (secure, insecure) = partition_secure(&jar);
+        assert_eq!(secure.len(), 6);
+        assert!(insecure.is_empty());
+
+        jar.add(Cookie::build("plain", "1").domain("www.pixiv.net").finish());
+        let (secure, insecure) = partition_secure(&jar);
+        assert_eq!(secure.len(), 6);
+        let names: Vec<_> = insecure.iter().map(|c| c.name()).collect();
+        assert_eq!(names, ["plain"]);
+    }
 }